@@ -272,6 +272,186 @@ class BlockchainService {
         throw new Error('Domain transfer requires transaction signing. This feature will be implemented with proper transaction building.');
     }
 
+    /**
+     * Parse listing account data:
+     * Listing {
+     *   domain: PublicKey (32 bytes),
+     *   seller: PublicKey (32 bytes),
+     *   mint: PublicKey (32 bytes),
+     *   price: u64 (8 bytes, lamports),
+     *   createdAt: i64 (8 bytes)
+     * }
+     */
+    parseListingAccount(data) {
+        const { PublicKey } = window.solanaWeb3;
+
+        // Skip 8-byte discriminator
+        let offset = 8;
+
+        const domain = new PublicKey(data.slice(offset, offset + 32)).toString();
+        offset += 32;
+        const seller = new PublicKey(data.slice(offset, offset + 32)).toString();
+        offset += 32;
+        const mint = new PublicKey(data.slice(offset, offset + 32)).toString();
+        offset += 32;
+
+        const price = Number(data.readBigUInt64LE(offset));
+        offset += 8;
+        const createdAt = Number(data.readBigInt64LE(offset));
+        offset += 8;
+
+        return { domain, seller, mint, price, createdAt };
+    }
+
+    /**
+     * Get active marketplace listings with their domains
+     */
+    async getListings() {
+        try {
+            if (!this.connection) {
+                await this.initialize();
+            }
+
+            // Listing accounts only: Anchor discriminator and fixed size
+            const accounts = await this.connection.getProgramAccounts(
+                this.programId,
+                {
+                    filters: [
+                        { memcmp: { offset: 0, bytes: LISTING_DISCRIMINATOR } },
+                        { dataSize: LISTING_ACCOUNT_SIZE }
+                    ]
+                }
+            );
+
+            const listings = [];
+
+            for (const { pubkey, account } of accounts) {
+                try {
+                    const listing = this.parseListingAccount(account.data);
+                    const domainInfo = await this.connection.getAccountInfo(
+                        new window.solanaWeb3.PublicKey(listing.domain)
+                    );
+                    if (!domainInfo) continue;
+
+                    // Listings whose domain was reclaimed no longer hold it
+                    const domain = this.parseDomainAccount(domainInfo.data, pubkey);
+                    if (!domain || !domain.isOwner || isDomainExpired(domain.expiresAt)) continue;
+
+                    listings.push({
+                        address: pubkey.toString(),
+                        domain: listing.domain,
+                        name: domain.name,
+                        tld: `.${domain.tld}`,
+                        price: listing.price / 1_000_000_000,
+                        priceLamports: listing.price,
+                        seller: listing.seller,
+                        listedDate: new Date(listing.createdAt * 1000),
+                        expiryDate: new Date(domain.expiresAt * 1000),
+                        nftMint: listing.mint
+                    });
+                } catch (parseError) {
+                    continue;
+                }
+            }
+
+            console.log(`Found ${listings.length} active listings`);
+            return listings;
+
+        } catch (error) {
+            console.error('Error fetching listings:', error);
+            return [];
+        }
+    }
+
+    /**
+     * Buy a marketplace listing. The buyer signs the transaction and agrees to
+     * pay at most the listed price, so a price raised before it lands fails.
+     */
+    async buyListing(listing) {
+        if (!this.connection) {
+            await this.initialize();
+        }
+        if (!this.wallet || !this.wallet.publicKey) {
+            throw new Error('Please connect your wallet first!');
+        }
+
+        const { PublicKey, SystemProgram, Transaction, TransactionInstruction } = window.solanaWeb3;
+        const encoder = new TextEncoder();
+        const buyer = this.wallet.publicKey;
+        const listingPDA = new PublicKey(listing.address);
+        const mint = new PublicKey(listing.nftMint);
+        const seller = new PublicKey(listing.seller);
+
+        // Fees go to the current treasury and TLD owner, read from their accounts
+        const servicePDA = await getServicePDA(PROGRAM_ID);
+        const tldPDA = await getTLDPDA(listing.tld.replace(/^\./, ''), PROGRAM_ID);
+        const [serviceInfo, tldInfo] = await Promise.all([
+            this.connection.getAccountInfo(servicePDA),
+            this.connection.getAccountInfo(tldPDA)
+        ]);
+        if (!serviceInfo || !tldInfo) {
+            throw new Error('Service or TLD account not found');
+        }
+
+        // Service { authority, treasury, ... }
+        const treasury = new PublicKey(serviceInfo.data.slice(40, 72));
+        // TLD { name: string, price: u64, owner, ... }
+        const tldOwnerOffset = 8 + 4 + tldInfo.data.readUInt32LE(8) + 8;
+        const tldOwner = new PublicKey(tldInfo.data.slice(tldOwnerOffset, tldOwnerOffset + 32));
+
+        const [sellerPrimary] = await PublicKey.findProgramAddress(
+            [encoder.encode('primary'), seller.toBuffer()],
+            this.programId
+        );
+
+        const accounts = {
+            listing: listingPDA,
+            domain: new PublicKey(listing.domain),
+            tld: tldPDA,
+            service: servicePDA,
+            mint,
+            escrowTokenAccount: await getAssociatedTokenAddress(mint, listingPDA),
+            seller,
+            sellerPrimary,
+            buyer,
+            buyerTokenAccount: await getAssociatedTokenAddress(mint, buyer),
+            treasury,
+            tldOwner,
+            tokenProgram: new PublicKey(TOKEN_PROGRAM_ID),
+            associatedTokenProgram: new PublicKey(ASSOCIATED_TOKEN_PROGRAM_ID),
+            systemProgram: SystemProgram.programId
+        };
+        const { accounts: accountMetas } = PROGRAM_IDL.instructions.find(
+            ix => ix.name === 'buyListingNft'
+        );
+
+        // Discriminator followed by max_price (u64, little-endian)
+        const data = new Uint8Array(16);
+        data.set(BUY_LISTING_NFT_DISCRIMINATOR, 0);
+        new DataView(data.buffer).setBigUint64(8, BigInt(listing.priceLamports), true);
+
+        const instruction = new TransactionInstruction({
+            programId: this.programId,
+            keys: accountMetas.map(({ name, isMut, isSigner }) => ({
+                pubkey: accounts[name],
+                isWritable: isMut,
+                isSigner
+            })),
+            data
+        });
+
+        const transaction = new Transaction().add(instruction);
+        transaction.feePayer = buyer;
+        const { blockhash } = await this.connection.getLatestBlockhash();
+        transaction.recentBlockhash = blockhash;
+
+        const { signature } = await this.wallet.signAndSendTransaction(transaction);
+        await this.connection.confirmTransaction(signature, 'confirmed');
+
+        console.log('Listing bought:', signature);
+        return signature;
+    }
+
     /**
      * Get all domains (for marketplace)
      */
@@ -24,6 +24,17 @@ const TLDS = [
     { name: 'pv', displayName: '.pv' }
 ];
 
+// Marketplace listing accounts (Anchor discriminator for `Listing`, base58)
+const LISTING_DISCRIMINATOR = 'dV6QTCMAagy';
+const LISTING_ACCOUNT_SIZE = 120; // 8 + 32 + 32 + 32 + 8 + 8
+
+// Anchor instruction discriminator for `buy_listing_nft` (first 8 bytes of sha256("global:buy_listing_nft"))
+const BUY_LISTING_NFT_DISCRIMINATOR = [124, 244, 0, 34, 172, 122, 39, 49];
+
+// SPL programs
+const TOKEN_PROGRAM_ID = 'TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA';
+const ASSOCIATED_TOKEN_PROGRAM_ID = 'ATokenGPvbdGVxr1b2hvZbsiqW4xWH25efTNsLJA8knL';
+
 // Program IDL (Interface Definition Language)
 const PROGRAM_IDL = {
     version: '0.1.0',
@@ -83,12 +94,36 @@ const PROGRAM_IDL = {
                 { name: 'domain', isMut: true, isSigner: false },
                 { name: 'service', isMut: true, isSigner: false },
                 { name: 'currentOwner', isMut: true, isSigner: true },
-                { name: 'newOwner', isMut: true, isSigner: false },
+                { name: 'newOwner', isMut: true, isSigner: true },
+                { name: 'treasury', isMut: true, isSigner: false },
+                { name: 'systemProgram', isMut: false, isSigner: false }
+            ],
+            args: [
+                { name: 'salePrice', type: 'u64' },
+                { name: 'maxPrice', type: 'u64' }
+            ]
+        },
+        {
+            name: 'buyListingNft',
+            accounts: [
+                { name: 'listing', isMut: true, isSigner: false },
+                { name: 'domain', isMut: true, isSigner: false },
+                { name: 'tld', isMut: false, isSigner: false },
+                { name: 'service', isMut: false, isSigner: false },
+                { name: 'mint', isMut: false, isSigner: false },
+                { name: 'escrowTokenAccount', isMut: true, isSigner: false },
+                { name: 'seller', isMut: true, isSigner: false },
+                { name: 'sellerPrimary', isMut: true, isSigner: false },
+                { name: 'buyer', isMut: true, isSigner: true },
+                { name: 'buyerTokenAccount', isMut: true, isSigner: false },
                 { name: 'treasury', isMut: true, isSigner: false },
+                { name: 'tldOwner', isMut: true, isSigner: false },
+                { name: 'tokenProgram', isMut: false, isSigner: false },
+                { name: 'associatedTokenProgram', isMut: false, isSigner: false },
                 { name: 'systemProgram', isMut: false, isSigner: false }
             ],
             args: [
-                { name: 'salePrice', type: 'u64' }
+                { name: 'maxPrice', type: 'u64' }
             ]
         }
     ],
@@ -159,6 +194,11 @@ if (typeof module !== 'undefined' && module.exports) {
         RENEWAL_FEE,
         ROYALTY_PERCENTAGE,
         TLDS,
+        LISTING_DISCRIMINATOR,
+        LISTING_ACCOUNT_SIZE,
+        BUY_LISTING_NFT_DISCRIMINATOR,
+        TOKEN_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID,
         PROGRAM_IDL
     };
 }
@@ -0,0 +1,236 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Pyth v2 price account layout (only the header and aggregate price are read)
+pub const PRICE_ACCOUNT_LEN: usize = 240;
+const MAGIC: u32 = 0xa1b2c3d4;
+const VERSION: u32 = 2;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPO_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+
+// 1 SOL = 10^9 lamports and 1 USD = 10^2 cents
+const LAMPORTS_PER_CENT_SCALE: u32 = 7;
+
+/// Aggregate SOL/USD price read from a Pyth-compatible price account.
+/// The price is `price * 10^expo` USD per SOL, `conf` is in the same units.
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl PriceFeed {
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        require!(data.len() >= PRICE_ACCOUNT_LEN, ErrorCode::InvalidPriceFeed);
+        require!(
+            read_u32(&data, MAGIC_OFFSET) == MAGIC
+                && read_u32(&data, VERSION_OFFSET) == VERSION
+                && read_u32(&data, ACCOUNT_TYPE_OFFSET) == ACCOUNT_TYPE_PRICE,
+            ErrorCode::InvalidPriceFeed
+        );
+        require!(
+            read_u32(&data, AGG_STATUS_OFFSET) == STATUS_TRADING,
+            ErrorCode::InvalidOraclePrice
+        );
+
+        Ok(PriceFeed {
+            price: read_u64(&data, AGG_PRICE_OFFSET) as i64,
+            conf: read_u64(&data, AGG_CONF_OFFSET),
+            expo: read_u32(&data, EXPO_OFFSET) as i32,
+            publish_time: read_u64(&data, TIMESTAMP_OFFSET) as i64,
+        })
+    }
+
+    /// Rejects prices that are non-positive, older than `max_age` seconds, or
+    /// whose confidence interval is wider than `max_confidence_bps` of the price.
+    pub fn check(&self, now: i64, max_age: i64, max_confidence_bps: u16) -> Result<()> {
+        require!(self.price > 0, ErrorCode::InvalidOraclePrice);
+        require!(
+            now.saturating_sub(self.publish_time) <= max_age,
+            ErrorCode::StaleOraclePrice
+        );
+
+        let max_conf = (self.price as u128) * (max_confidence_bps as u128);
+        require!(
+            (self.conf as u128) * 10_000 <= max_conf,
+            ErrorCode::OracleConfidenceTooWide
+        );
+        Ok(())
+    }
+
+    /// Converts USD cents to lamports at this price, rounding up so fees
+    /// are never undercharged.
+    pub fn usd_cents_to_lamports(&self, cents: u64) -> Result<u64> {
+        // lamports = cents * 10^7 / (price * 10^expo)
+        let exponent = LAMPORTS_PER_CENT_SCALE as i64 - self.expo as i64;
+        let (numerator_scale, denominator_scale) = if exponent >= 0 {
+            (pow10(exponent)?, 1)
+        } else {
+            (1, pow10(-exponent)?)
+        };
+
+        let numerator = (cents as u128)
+            .checked_mul(numerator_scale)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let denominator = (self.price as u128)
+            .checked_mul(denominator_scale)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let lamports = numerator
+            .checked_add(denominator - 1)
+            .ok_or(ErrorCode::MathOverflow)?
+            / denominator;
+
+        u64::try_from(lamports).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+fn pow10(exponent: i64) -> Result<u128> {
+    u32::try_from(exponent)
+        .ok()
+        .and_then(|exponent| 10u128.checked_pow(exponent))
+        .ok_or(error!(ErrorCode::MathOverflow))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn feed(price: i64, conf: u64, expo: i32) -> PriceFeed {
+        PriceFeed {
+            price,
+            conf,
+            expo,
+            publish_time: NOW,
+        }
+    }
+
+    /// A price account in the layout `load` reads, marked as trading.
+    fn price_account(feed: &PriceFeed) -> Vec<u8> {
+        let mut data = vec![0; PRICE_ACCOUNT_LEN];
+        let mut write = |offset: usize, bytes: &[u8]| {
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        write(MAGIC_OFFSET, &MAGIC.to_le_bytes());
+        write(VERSION_OFFSET, &VERSION.to_le_bytes());
+        write(ACCOUNT_TYPE_OFFSET, &ACCOUNT_TYPE_PRICE.to_le_bytes());
+        write(EXPO_OFFSET, &feed.expo.to_le_bytes());
+        write(TIMESTAMP_OFFSET, &feed.publish_time.to_le_bytes());
+        write(AGG_PRICE_OFFSET, &feed.price.to_le_bytes());
+        write(AGG_CONF_OFFSET, &feed.conf.to_le_bytes());
+        write(AGG_STATUS_OFFSET, &STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    fn load(mut data: Vec<u8>) -> Result<PriceFeed> {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        PriceFeed::load(&info)
+    }
+
+    fn assert_rejected<T>(result: Result<T>, expected: ErrorCode) {
+        assert_eq!(result.err().unwrap(), error!(expected));
+    }
+
+    #[test]
+    fn loads_trading_price_accounts() {
+        let loaded = load(price_account(&feed(15_000_000_000, 2_500_000, -8))).unwrap();
+        assert_eq!(loaded.price, 15_000_000_000);
+        assert_eq!(loaded.conf, 2_500_000);
+        assert_eq!(loaded.expo, -8);
+        assert_eq!(loaded.publish_time, NOW);
+    }
+
+    #[test]
+    fn rejects_accounts_that_are_not_price_accounts() {
+        let data = price_account(&feed(15_000_000_000, 0, -8));
+        assert_rejected(load(data[..PRICE_ACCOUNT_LEN - 1].to_vec()), ErrorCode::InvalidPriceFeed);
+
+        for offset in [MAGIC_OFFSET, VERSION_OFFSET, ACCOUNT_TYPE_OFFSET] {
+            let mut data = data.clone();
+            data[offset] ^= 0xff;
+            assert_rejected(load(data), ErrorCode::InvalidPriceFeed);
+        }
+    }
+
+    #[test]
+    fn rejects_prices_that_are_not_trading() {
+        let mut data = price_account(&feed(15_000_000_000, 0, -8));
+        data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4].copy_from_slice(&0u32.to_le_bytes());
+        assert_rejected(load(data), ErrorCode::InvalidOraclePrice);
+    }
+
+    #[test]
+    fn rejects_non_positive_prices() {
+        assert_rejected(feed(0, 0, -8).check(NOW, 60, 200), ErrorCode::InvalidOraclePrice);
+        assert_rejected(feed(-1, 0, -8).check(NOW, 60, 200), ErrorCode::InvalidOraclePrice);
+    }
+
+    #[test]
+    fn rejects_stale_prices() {
+        let feed = feed(15_000_000_000, 0, -8);
+        assert!(feed.check(NOW, 60, 200).is_ok());
+        assert!(feed.check(NOW + 60, 60, 200).is_ok());
+        assert_rejected(feed.check(NOW + 61, 60, 200), ErrorCode::StaleOraclePrice);
+    }
+
+    #[test]
+    fn bounds_the_confidence_interval() {
+        // 2% of 150.00000000 is 3.00000000
+        assert!(feed(15_000_000_000, 300_000_000, -8).check(NOW, 60, 200).is_ok());
+        assert_rejected(
+            feed(15_000_000_000, 300_000_001, -8).check(NOW, 60, 200),
+            ErrorCode::OracleConfidenceTooWide,
+        );
+        assert_rejected(
+            feed(15_000_000_000, 1, -8).check(NOW, 60, 0),
+            ErrorCode::OracleConfidenceTooWide,
+        );
+    }
+
+    #[test]
+    fn converts_independently_of_the_exponent() {
+        // $150 per SOL, whatever the exponent: $1.50 buys 0.01 SOL
+        for (price, expo) in [(15_000_000_000, -8), (15_000, -2), (150, 0), (15, 1)] {
+            assert_eq!(feed(price, 0, expo).usd_cents_to_lamports(150).unwrap(), 10_000_000, "{expo}");
+        }
+    }
+
+    #[test]
+    fn rounds_conversions_up() {
+        // $10 at $150 per SOL is 66_666_666.67 lamports
+        let feed = feed(15_000_000_000, 0, -8);
+        assert_eq!(feed.usd_cents_to_lamports(1_000).unwrap(), 66_666_667);
+        assert_eq!(feed.usd_cents_to_lamports(1).unwrap(), 66_667);
+        assert_eq!(feed.usd_cents_to_lamports(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_conversions_that_overflow() {
+        assert_rejected(feed(1, 0, -40).usd_cents_to_lamports(1), ErrorCode::MathOverflow);
+        assert_rejected(feed(1, 0, -8).usd_cents_to_lamports(u64::MAX), ErrorCode::MathOverflow);
+    }
+}
@@ -0,0 +1,381 @@
+//! Account validation for instruction contexts. Each context must reject a
+//! service, TLD, treasury, TLD owner or role account that doesn't match the
+//! program's own configuration, so callers cannot redirect fees to themselves.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::AccountMeta, system_program};
+use digitalpulse_tld::{
+    accounts, instruction, Auction, Role, RoleKind, Service, PAUSE_MARKETPLACE, TLD,
+};
+
+use crate::harness::{assert_error, pda, AccountState, Fixture, LAMPORTS};
+
+const YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// Stores a copy of the genuine service at an address that isn't its PDA.
+fn copy_service(f: &mut Fixture, tamper: impl FnOnce(&mut Service)) -> Pubkey {
+    let key = Pubkey::new_unique();
+    let mut service = f.service();
+    tamper(&mut service);
+    f.bank.store_with_space(&key, &service, Service::SPACE);
+    key
+}
+
+/// Moves the program state at `key` under another program.
+fn reassign<T: AccountSerialize + AccountDeserialize>(f: &mut Fixture, key: Pubkey) {
+    let mut data = Vec::new();
+    f.bank.load::<T>(&key).try_serialize(&mut data).unwrap();
+    f.bank.set(
+        key,
+        AccountState {
+            lamports: f.bank.lamports(&key),
+            data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+        },
+    );
+}
+
+/// A genuine TLD under another name, to stand in for a domain's own TLD.
+fn other_tld(f: &mut Fixture) -> Pubkey {
+    let key = pda(&[b"tld", b"verse"]);
+    let tld = TLD {
+        name: "verse".to_string(),
+        ..f.tld()
+    };
+    f.bank.store(&key, &tld);
+    key
+}
+
+fn operator_role(f: &mut Fixture, holder: Pubkey) -> Pubkey {
+    let key = pda(&[b"role", holder.as_ref(), &[RoleKind::Operator as u8]]);
+    let role = Role {
+        holder,
+        kind: RoleKind::Operator,
+        granted_by: f.authority,
+        granted_at: f.bank.now,
+    };
+    f.bank.store(&key, &role);
+    key
+}
+
+/// Passes a context's accounts with `key` no longer signing.
+struct Unsigned<T>(T, Pubkey);
+
+impl<T: ToAccountMetas> ToAccountMetas for Unsigned<T> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        let mut metas = self.0.to_account_metas(is_signer);
+        for meta in metas.iter_mut().filter(|meta| meta.pubkey == self.1) {
+            meta.is_signer = false;
+        }
+        metas
+    }
+}
+
+fn renew_accounts(f: &mut Fixture) -> (accounts::RenewDomain, Pubkey) {
+    let owner = f.wallet();
+    let expires_at = f.bank.now + YEAR;
+    let domain = f.add_domain("alice", owner, expires_at);
+    let accounts = accounts::RenewDomain {
+        domain,
+        tld: f.tld_key(),
+        service: f.service_key(),
+        owner,
+        owner_role: None,
+        treasury: f.treasury,
+        tld_owner: f.tld_owner,
+        price_feed: None,
+        token_price: None,
+        payer_payment_account: None,
+        treasury_payment_account: None,
+        tld_owner_payment_account: None,
+        token_program: None,
+        system_program: system_program::ID,
+    };
+    (accounts, owner)
+}
+
+fn renew(f: &mut Fixture, accounts: accounts::RenewDomain) -> std::result::Result<(), ProgramError> {
+    f.bank.process(accounts, instruction::RenewDomain { years: 1 })
+}
+
+fn transfer_accounts(f: &mut Fixture) -> accounts::TransferDomain {
+    let owner = f.wallet();
+    let expires_at = f.bank.now + YEAR;
+    let domain = f.add_domain("alice", owner, expires_at);
+    accounts::TransferDomain {
+        domain,
+        tld: f.tld_key(),
+        service: f.service_key(),
+        current_owner: owner,
+        current_owner_primary: pda(&[b"primary", owner.as_ref()]),
+        new_owner: f.wallet(),
+        treasury: f.treasury,
+        tld_owner: f.tld_owner,
+        token_price: None,
+        payer_payment_account: None,
+        treasury_payment_account: None,
+        tld_owner_payment_account: None,
+        seller_payment_account: None,
+        token_program: None,
+        system_program: system_program::ID,
+    }
+}
+
+fn transfer(
+    f: &mut Fixture,
+    accounts: impl ToAccountMetas,
+) -> std::result::Result<(), ProgramError> {
+    f.bank.process(
+        accounts,
+        instruction::TransferDomain {
+            sale_price: LAMPORTS,
+            max_price: LAMPORTS,
+        },
+    )
+}
+
+/// An ended auction of `alice.pulse` holding one escrowed bid.
+fn ended_auction(f: &mut Fixture) -> (accounts::SettleAuction, Pubkey) {
+    let seller = f.wallet();
+    let bidder = f.wallet();
+    let domain = f.domain_key("alice");
+    let auction = pda(&[b"auction", domain.as_ref()]);
+    f.add_domain("alice", auction, f.bank.now + YEAR);
+    f.bank.store(
+        &auction,
+        &Auction {
+            domain,
+            seller,
+            reserve_price: LAMPORTS,
+            min_increment: 1,
+            end_time: f.bank.now,
+            highest_bidder: Some(bidder),
+            highest_bid: LAMPORTS,
+            bump: 255,
+        },
+    );
+    f.bank.fund(auction, LAMPORTS);
+
+    let accounts = accounts::SettleAuction {
+        auction,
+        domain,
+        tld: f.tld_key(),
+        service: f.service_key(),
+        seller,
+        seller_primary: pda(&[b"primary", seller.as_ref()]),
+        recipient: bidder,
+        treasury: f.treasury,
+        tld_owner: f.tld_owner,
+    };
+    (accounts, bidder)
+}
+
+#[test]
+fn renew_domain_accepts_configured_accounts() {
+    let mut f = Fixture::new();
+    let (accounts, _) = renew_accounts(&mut f);
+    renew(&mut f, accounts).unwrap();
+}
+
+#[test]
+fn renew_domain_rejects_service_at_another_address() {
+    let mut f = Fixture::new();
+    let (mut accounts, _) = renew_accounts(&mut f);
+    accounts.service = copy_service(&mut f, |_| {});
+    assert_error(renew(&mut f, accounts), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn renew_domain_rejects_service_owned_by_another_program() {
+    let mut f = Fixture::new();
+    let (accounts, _) = renew_accounts(&mut f);
+    let service = f.service_key();
+    reassign::<Service>(&mut f, service);
+    assert_error(renew(&mut f, accounts), AnchorErrorCode::AccountOwnedByWrongProgram);
+}
+
+#[test]
+fn renew_domain_accepts_operator_role() {
+    let mut f = Fixture::new();
+    let (mut accounts, owner) = renew_accounts(&mut f);
+    accounts.owner_role = Some(operator_role(&mut f, owner));
+    renew(&mut f, accounts).unwrap();
+}
+
+#[test]
+fn renew_domain_rejects_role_owned_by_another_program() {
+    // A forged operator role would waive the renewal fee
+    let mut f = Fixture::new();
+    let (mut accounts, owner) = renew_accounts(&mut f);
+    let role = operator_role(&mut f, owner);
+    reassign::<Role>(&mut f, role);
+    accounts.owner_role = Some(role);
+    assert_error(renew(&mut f, accounts), AnchorErrorCode::AccountOwnedByWrongProgram);
+}
+
+#[test]
+fn renew_domain_rejects_caller_supplied_treasury() {
+    let mut f = Fixture::new();
+    let (mut accounts, _) = renew_accounts(&mut f);
+    accounts.treasury = f.wallet();
+    assert_error(renew(&mut f, accounts), AnchorErrorCode::ConstraintAddress);
+}
+
+#[test]
+fn renew_domain_rejects_tld_of_another_name() {
+    let mut f = Fixture::new();
+    let (mut accounts, _) = renew_accounts(&mut f);
+    accounts.tld = other_tld(&mut f);
+    assert_error(renew(&mut f, accounts), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn renew_domain_rejects_caller_supplied_tld_owner() {
+    let mut f = Fixture::new();
+    let (mut accounts, _) = renew_accounts(&mut f);
+    accounts.tld_owner = f.wallet();
+    assert_error(renew(&mut f, accounts), AnchorErrorCode::ConstraintAddress);
+}
+
+#[test]
+fn transfer_domain_accepts_configured_accounts() {
+    let mut f = Fixture::new();
+    let accounts = transfer_accounts(&mut f);
+    let new_owner = accounts.new_owner;
+    transfer(&mut f, accounts).unwrap();
+    assert_eq!(f.domain("alice").owner, new_owner);
+}
+
+#[test]
+fn transfer_domain_rejects_service_at_another_address() {
+    let mut f = Fixture::new();
+    let mut accounts = transfer_accounts(&mut f);
+    accounts.service = copy_service(&mut f, |_| {});
+    assert_error(transfer(&mut f, accounts), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn transfer_domain_rejects_caller_supplied_treasury() {
+    let mut f = Fixture::new();
+    let mut accounts = transfer_accounts(&mut f);
+    accounts.treasury = f.wallet();
+    assert_error(transfer(&mut f, accounts), AnchorErrorCode::ConstraintAddress);
+}
+
+#[test]
+fn transfer_domain_rejects_tld_of_another_name() {
+    let mut f = Fixture::new();
+    let mut accounts = transfer_accounts(&mut f);
+    accounts.tld = other_tld(&mut f);
+    assert_error(transfer(&mut f, accounts), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn transfer_domain_requires_buyer_signature() {
+    let mut f = Fixture::new();
+    let accounts = transfer_accounts(&mut f);
+    let new_owner = accounts.new_owner;
+    assert_error(
+        transfer(&mut f, Unsigned(accounts, new_owner)),
+        AnchorErrorCode::AccountNotSigner,
+    );
+}
+
+#[test]
+fn place_bid_rejects_service_at_another_address() {
+    // An unpaused copy of the service must not get around a marketplace pause
+    let mut f = Fixture::new();
+    let (settle, bidder) = ended_auction(&mut f);
+    let mut service = f.service();
+    service.paused = PAUSE_MARKETPLACE;
+    f.set_service(&service);
+    let mut auction = f.bank.load::<Auction>(&settle.auction);
+    auction.end_time = f.bank.now + YEAR;
+    f.bank.store(&settle.auction, &auction);
+    f.bank.fund(settle.auction, LAMPORTS);
+
+    let accounts = accounts::PlaceBid {
+        auction: settle.auction,
+        domain: settle.domain,
+        service: copy_service(&mut f, |service| service.paused = 0),
+        bidder: f.wallet(),
+        previous_bidder: Some(bidder),
+        system_program: system_program::ID,
+    };
+    assert_error(
+        f.bank
+            .process(accounts, instruction::PlaceBid { amount: 2 * LAMPORTS }),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn settle_auction_accepts_configured_accounts() {
+    let mut f = Fixture::new();
+    let (accounts, bidder) = ended_auction(&mut f);
+    f.bank.process(accounts, instruction::SettleAuction {}).unwrap();
+    assert_eq!(f.domain("alice").owner, bidder);
+}
+
+#[test]
+fn settle_auction_rejects_caller_supplied_treasury() {
+    let mut f = Fixture::new();
+    let (mut accounts, bidder) = ended_auction(&mut f);
+    accounts.treasury = bidder;
+    assert_error(
+        f.bank.process(accounts, instruction::SettleAuction {}),
+        AnchorErrorCode::ConstraintAddress,
+    );
+}
+
+#[test]
+fn settle_auction_rejects_tld_of_another_name() {
+    let mut f = Fixture::new();
+    let (mut accounts, _) = ended_auction(&mut f);
+    accounts.tld = other_tld(&mut f);
+    assert_error(
+        f.bank.process(accounts, instruction::SettleAuction {}),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn authority_handover_rejects_service_at_another_address() {
+    // A copy naming the caller as authority must not stand in for the service
+    let mut f = Fixture::new();
+    let caller = f.wallet();
+    let service = copy_service(&mut f, |service| {
+        service.authority = caller;
+        service.pending_authority = Some(caller);
+    });
+
+    let accounts = accounts::ProposeAuthority {
+        service,
+        authority: caller,
+    };
+    assert_error(
+        f.bank
+            .process(accounts, instruction::ProposeAuthority { new_authority: caller }),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+    let accounts = accounts::AcceptAuthority {
+        service,
+        new_authority: caller,
+    };
+    assert_error(
+        f.bank.process(accounts, instruction::AcceptAuthority {}),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+    let accounts = accounts::ProposeTreasury {
+        service,
+        authority: caller,
+    };
+    assert_error(
+        f.bank
+            .process(accounts, instruction::ProposeTreasury { new_treasury: caller }),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+    assert_eq!(f.service().authority, f.authority);
+}
@@ -0,0 +1,163 @@
+//! Two-step authority and treasury handover.
+
+use anchor_lang::prelude::*;
+use digitalpulse_tld::{accounts, instruction, ErrorCode};
+
+use crate::harness::{assert_error, Fixture};
+
+fn propose_authority(
+    f: &mut Fixture,
+    authority: Pubkey,
+    new_authority: Pubkey,
+) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::ProposeAuthority {
+        service: f.service_key(),
+        authority,
+    };
+    f.bank
+        .process(accounts, instruction::ProposeAuthority { new_authority })
+}
+
+fn accept_authority(f: &mut Fixture, new_authority: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::AcceptAuthority {
+        service: f.service_key(),
+        new_authority,
+    };
+    f.bank.process(accounts, instruction::AcceptAuthority {})
+}
+
+fn propose_treasury(
+    f: &mut Fixture,
+    authority: Pubkey,
+    new_treasury: Pubkey,
+) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::ProposeTreasury {
+        service: f.service_key(),
+        authority,
+    };
+    f.bank
+        .process(accounts, instruction::ProposeTreasury { new_treasury })
+}
+
+fn accept_treasury(f: &mut Fixture, new_treasury: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::AcceptTreasury {
+        service: f.service_key(),
+        new_treasury,
+    };
+    f.bank.process(accounts, instruction::AcceptTreasury {})
+}
+
+#[test]
+fn proposed_authority_takes_over_once_it_accepts() {
+    let mut f = Fixture::new();
+    let authority = f.authority;
+    let new_authority = Pubkey::new_unique();
+
+    propose_authority(&mut f, authority, new_authority).unwrap();
+    let service = f.service();
+    assert_eq!(service.authority, authority);
+    assert_eq!(service.pending_authority, Some(new_authority));
+
+    accept_authority(&mut f, new_authority).unwrap();
+    let service = f.service();
+    assert_eq!(service.authority, new_authority);
+    assert_eq!(service.pending_authority, None);
+}
+
+#[test]
+fn only_the_proposed_authority_can_accept() {
+    let mut f = Fixture::new();
+    let authority = f.authority;
+    let new_authority = Pubkey::new_unique();
+    propose_authority(&mut f, authority, new_authority).unwrap();
+
+    assert_error(accept_authority(&mut f, Pubkey::new_unique()), ErrorCode::Unauthorized);
+    assert_error(accept_authority(&mut f, authority), ErrorCode::Unauthorized);
+    assert_eq!(f.service().authority, authority);
+    assert_eq!(f.service().pending_authority, Some(new_authority));
+}
+
+#[test]
+fn cancelled_authority_proposal_cannot_be_accepted() {
+    let mut f = Fixture::new();
+    let authority = f.authority;
+    let new_authority = Pubkey::new_unique();
+    propose_authority(&mut f, authority, new_authority).unwrap();
+
+    let accounts = accounts::CancelAuthorityProposal {
+        service: f.service_key(),
+        authority,
+    };
+    f.bank
+        .process(accounts, instruction::CancelAuthorityProposal {})
+        .unwrap();
+
+    assert_eq!(f.service().pending_authority, None);
+    assert_error(accept_authority(&mut f, new_authority), ErrorCode::NoPendingAuthority);
+    assert_eq!(f.service().authority, authority);
+}
+
+#[test]
+fn previous_authority_loses_access_after_handover() {
+    let mut f = Fixture::new();
+    let authority = f.authority;
+    let new_authority = Pubkey::new_unique();
+    propose_authority(&mut f, authority, new_authority).unwrap();
+    accept_authority(&mut f, new_authority).unwrap();
+
+    assert_error(
+        propose_authority(&mut f, authority, authority),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        propose_treasury(&mut f, authority, Pubkey::new_unique()),
+        ErrorCode::Unauthorized,
+    );
+    propose_authority(&mut f, new_authority, authority).unwrap();
+}
+
+#[test]
+fn only_the_authority_can_propose() {
+    let mut f = Fixture::new();
+    let outsider = Pubkey::new_unique();
+
+    assert_error(propose_authority(&mut f, outsider, outsider), ErrorCode::Unauthorized);
+    assert_error(propose_treasury(&mut f, outsider, outsider), ErrorCode::Unauthorized);
+    assert_eq!(f.service().pending_authority, None);
+    assert_eq!(f.service().pending_treasury, None);
+}
+
+#[test]
+fn proposed_treasury_takes_over_once_it_accepts() {
+    let mut f = Fixture::new();
+    let (authority, treasury) = (f.authority, f.treasury);
+    let new_treasury = Pubkey::new_unique();
+    propose_treasury(&mut f, authority, new_treasury).unwrap();
+
+    assert_error(accept_treasury(&mut f, treasury), ErrorCode::Unauthorized);
+    assert_eq!(f.service().treasury, treasury);
+
+    accept_treasury(&mut f, new_treasury).unwrap();
+    let service = f.service();
+    assert_eq!(service.treasury, new_treasury);
+    assert_eq!(service.pending_treasury, None);
+}
+
+#[test]
+fn cancelled_treasury_proposal_cannot_be_accepted() {
+    let mut f = Fixture::new();
+    let authority = f.authority;
+    let new_treasury = Pubkey::new_unique();
+    propose_treasury(&mut f, authority, new_treasury).unwrap();
+
+    let accounts = accounts::CancelTreasuryProposal {
+        service: f.service_key(),
+        authority,
+    };
+    f.bank
+        .process(accounts, instruction::CancelTreasuryProposal {})
+        .unwrap();
+
+    assert_error(accept_treasury(&mut f, new_treasury), ErrorCode::NoPendingTreasury);
+    assert_eq!(f.service().treasury, f.treasury);
+}
@@ -0,0 +1,562 @@
+//! A minimal in-process runtime. Accounts are serialized in the BPF loader's
+//! input format, so `realloc`, `assign` and account closing behave as they do
+//! on chain, and CPIs into the system and SPL token programs are executed by
+//! syscall stubs.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable,
+    entrypoint::{self, ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    program_utils::limited_deserialize,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use digitalpulse_tld::{accounts, instruction, name_hash, Domain, Service, ID, TLD};
+
+pub const LAMPORTS: u64 = 1_000_000_000;
+pub const START: i64 = 1_700_000_000;
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(START) };
+    static PROGRAMS: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW.with(Cell::get),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|data| data.borrow().clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program = PROGRAMS.with(|programs| *programs.borrow().last().unwrap());
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = Some((program, data.to_vec())));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = PROGRAMS.with(|programs| *programs.borrow().last().unwrap());
+        let pda_signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        let mut accounts = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let mut info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?
+                .clone();
+            if meta.is_signer && !info.is_signer && !pda_signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            info.is_signer = meta.is_signer;
+            info.is_writable = meta.is_writable;
+            accounts.push(info);
+        }
+
+        PROGRAMS.with(|programs| programs.borrow_mut().push(instruction.program_id));
+        let result = if instruction.program_id == system_program::ID {
+            process_system(&accounts, &instruction.data)
+        } else if instruction.program_id == spl_token::ID {
+            spl_token::processor::Processor::process(&spl_token::ID, &accounts, &instruction.data)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        };
+        PROGRAMS.with(|programs| programs.borrow_mut().pop());
+        result
+    }
+}
+
+fn debit(account: &AccountInfo, lamports: u64) -> ProgramResult {
+    let balance = account.lamports();
+    **account.try_borrow_mut_lamports()? =
+        balance.checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    Ok(())
+}
+
+fn credit(account: &AccountInfo, lamports: u64) -> ProgramResult {
+    let balance = account.lamports();
+    **account.try_borrow_mut_lamports()? =
+        balance.checked_add(lamports).ok_or(ProgramError::InvalidArgument)?;
+    Ok(())
+}
+
+/// The subset of the system program Anchor's `init` and transfers rely on.
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let is_unused = |account: &AccountInfo| {
+        account.data_is_empty() && *account.owner == system_program::ID
+    };
+
+    let instruction = limited_deserialize(data, data.len() as u64)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            if !is_unused(to) || to.lamports() > 0 {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            debit(from, lamports)?;
+            credit(to, lamports)?;
+            to.realloc(space as usize, true)?;
+            to.assign(&owner);
+        }
+        SystemInstruction::Transfer { lamports } => {
+            if !is_unused(&accounts[0]) {
+                return Err(ProgramError::InvalidArgument);
+            }
+            debit(&accounts[0], lamports)?;
+            credit(&accounts[1], lamports)?;
+        }
+        SystemInstruction::Allocate { space } => {
+            if !is_unused(&accounts[0]) {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            accounts[0].realloc(space as usize, true)?;
+        }
+        SystemInstruction::Assign { owner } => {
+            if *accounts[0].owner != system_program::ID {
+                return Err(ProgramError::IllegalOwner);
+            }
+            accounts[0].assign(&owner);
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Accounts keyed by address, updated only by instructions that succeed.
+pub struct Bank {
+    accounts: HashMap<Pubkey, AccountState>,
+    pub now: i64,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut bank = Self {
+            accounts: HashMap::new(),
+            now: START,
+        };
+        for program in [ID, system_program::ID, spl_token::ID] {
+            bank.set(
+                program,
+                AccountState {
+                    lamports: 1,
+                    data: Vec::new(),
+                    owner: bpf_loader_upgradeable::ID,
+                    executable: true,
+                },
+            );
+        }
+        bank
+    }
+
+    pub fn set(&mut self, key: Pubkey, account: AccountState) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.contains_key(key)
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    /// Creates or tops up a system-owned wallet.
+    pub fn fund(&mut self, key: Pubkey, lamports: u64) {
+        let account = self.accounts.entry(key).or_insert(AccountState {
+            lamports: 0,
+            data: Vec::new(),
+            owner: system_program::ID,
+            executable: false,
+        });
+        account.lamports += lamports;
+    }
+
+    /// Stores a rent-exempt account owned by `owner` holding `data`.
+    pub fn store_data(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set(
+            key,
+            AccountState {
+                lamports,
+                data,
+                owner,
+                executable: false,
+            },
+        );
+    }
+
+    /// Stores program state, as if an earlier instruction had created it.
+    pub fn store<T: AccountSerialize>(&mut self, key: &Pubkey, value: &T) {
+        self.store_with_space(key, value, 0);
+    }
+
+    /// Stores program state in an account of at least `space` bytes, leaving
+    /// room for fields that grow, such as options that are currently `None`.
+    pub fn store_with_space<T: AccountSerialize>(&mut self, key: &Pubkey, value: &T, space: usize) {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        data.resize(data.len().max(space), 0);
+        self.store_data(*key, ID, data);
+    }
+
+    pub fn load<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.accounts.get(key).expect("account does not exist");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub fn create_mint(&mut self, mint: Pubkey) {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            supply: u64::MAX / 2,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.store_data(mint, spl_token::ID, data);
+    }
+
+    pub fn create_token_account(&mut self, key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        self.store_data(key, spl_token::ID, data);
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        let account = self.accounts.get(key).expect("token account does not exist");
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    /// Runs one instruction of this program. Account changes are kept only if
+    /// it succeeds, and accounts left without lamports are removed.
+    pub fn process(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        args: impl InstructionData,
+    ) -> std::result::Result<(), ProgramError> {
+        let metas = accounts.to_account_metas(None);
+
+        // Duplicate keys share one account with the union of their privileges
+        let mut keys: Vec<Pubkey> = Vec::new();
+        let mut positions = Vec::with_capacity(metas.len());
+        let mut privileges: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+        for meta in &metas {
+            let entry = privileges.entry(meta.pubkey).or_default();
+            entry.0 |= meta.is_signer;
+            entry.1 |= meta.is_writable;
+            positions.push(match metas.iter().position(|m| m.pubkey == meta.pubkey) {
+                Some(first) if first < positions.len() => Some(first),
+                _ => {
+                    keys.push(meta.pubkey);
+                    None
+                }
+            });
+        }
+
+        let mut input = Vec::new();
+        input.extend_from_slice(&(metas.len() as u64).to_le_bytes());
+        for (meta, duplicate_of) in metas.iter().zip(&positions) {
+            if let Some(first) = duplicate_of {
+                input.push(*first as u8);
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            let account = self.accounts.get(&meta.pubkey).cloned().unwrap_or(AccountState {
+                lamports: 0,
+                data: Vec::new(),
+                owner: system_program::ID,
+                executable: false,
+            });
+            let (is_signer, is_writable) = privileges[&meta.pubkey];
+            input.extend_from_slice(&[NON_DUP_MARKER, is_signer as u8, is_writable as u8, account.executable as u8]);
+            input.extend_from_slice(&[0; size_of::<u32>()]);
+            input.extend_from_slice(meta.pubkey.as_ref());
+            input.extend_from_slice(account.owner.as_ref());
+            input.extend_from_slice(&account.lamports.to_le_bytes());
+            input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&account.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+            input.extend_from_slice(&0u64.to_le_bytes());
+        }
+        let data = args.data();
+        input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&data);
+        input.extend_from_slice(ID.as_ref());
+
+        // Deserialization reads u64s in place, so the buffer must be 8-byte aligned
+        let mut buffer = vec![0u64; input.len().div_ceil(size_of::<u64>())];
+        unsafe {
+            std::ptr::copy_nonoverlapping(input.as_ptr(), buffer.as_mut_ptr() as *mut u8, input.len());
+        }
+
+        NOW.with(|now| now.set(self.now));
+        RETURN_DATA.with(|data| *data.borrow_mut() = None);
+        PROGRAMS.with(|programs| programs.borrow_mut().push(ID));
+        let (program_id, infos, data) = unsafe { entrypoint::deserialize(buffer.as_mut_ptr() as *mut u8) };
+        let result = digitalpulse_tld::entry(program_id, &infos, data);
+        PROGRAMS.with(|programs| programs.borrow_mut().pop());
+        result?;
+
+        let lamports_before: u64 = keys.iter().map(|key| self.lamports(key)).sum();
+        let lamports_after: u64 = keys
+            .iter()
+            .map(|key| infos.iter().find(|info| info.key == key).unwrap().lamports())
+            .sum();
+        assert_eq!(lamports_before, lamports_after, "instruction created or destroyed lamports");
+
+        for key in keys {
+            let info = infos.iter().find(|info| *info.key == key).unwrap();
+            if info.lamports() == 0 {
+                self.accounts.remove(&key);
+            } else {
+                self.accounts.insert(
+                    key,
+                    AccountState {
+                        lamports: info.lamports(),
+                        data: info.data.borrow().to_vec(),
+                        owner: *info.owner,
+                        executable: info.executable,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+pub fn assert_error(
+    result: std::result::Result<(), ProgramError>,
+    expected: impl Into<anchor_lang::error::Error>,
+) {
+    assert_eq!(result.unwrap_err(), ProgramError::from(expected.into()));
+}
+
+/// A service with a `pulse` TLD, and funded wallets for its roles.
+pub struct Fixture {
+    pub bank: Bank,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub tld_owner: Pubkey,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let mut bank = Bank::new();
+        let authority = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let tld_owner = Pubkey::new_unique();
+        for wallet in [authority, treasury, tld_owner] {
+            bank.fund(wallet, 100 * LAMPORTS);
+        }
+
+        bank.store_with_space(
+            &Self::service_key_static(),
+            &Service {
+                authority,
+                treasury,
+                total_domains: 0,
+                total_tlds: 1,
+                grace_period: 30 * 24 * 60 * 60,
+                max_prepaid_years: 10,
+                price_feed: Pubkey::default(),
+                max_price_age: 60,
+                max_price_confidence_bps: 200,
+                pending_authority: None,
+                pending_treasury: None,
+                guardian: Pubkey::default(),
+                paused: 0,
+            },
+            Service::SPACE,
+        );
+        bank.store(
+            &pda(&[b"tld", b"pulse"]),
+            &TLD {
+                name: "pulse".to_string(),
+                price: LAMPORTS,
+                owner: tld_owner,
+                created_at: START,
+                is_active: true,
+                total_domains: 0,
+                renewal_price: LAMPORTS,
+                allow_idna: false,
+                usd_pricing: false,
+                release_start_price: 0,
+                release_period: 0,
+                owner_share_bps: 1_000,
+            },
+        );
+
+        Self {
+            bank,
+            authority,
+            treasury,
+            tld_owner,
+        }
+    }
+
+    fn service_key_static() -> Pubkey {
+        pda(&[b"service"])
+    }
+
+    pub fn service_key(&self) -> Pubkey {
+        Self::service_key_static()
+    }
+
+    pub fn service(&self) -> Service {
+        self.bank.load(&self.service_key())
+    }
+
+    pub fn set_service(&mut self, service: &Service) {
+        let key = self.service_key();
+        self.bank.store_with_space(&key, service, Service::SPACE);
+    }
+
+    pub fn tld_key(&self) -> Pubkey {
+        pda(&[b"tld", b"pulse"])
+    }
+
+    pub fn tld(&self) -> TLD {
+        self.bank.load(&self.tld_key())
+    }
+
+    pub fn domain_key(&self, name: &str) -> Pubkey {
+        pda(&[b"domain", name.as_bytes(), b"pulse"])
+    }
+
+    pub fn domain(&self, name: &str) -> Domain {
+        self.bank.load(&self.domain_key(name))
+    }
+
+    /// Accounts to register `name`.pulse, paying in SOL.
+    pub fn register_accounts(&self, name: &str, owner: Pubkey) -> accounts::RegisterDomain {
+        let tld = self.tld_key();
+        accounts::RegisterDomain {
+            domain: self.domain_key(name),
+            tld,
+            pricing_schedule: pda(&[b"pricing", tld.as_ref()]),
+            premium_name: pda(&[b"premium", tld.as_ref(), &name_hash(name)]),
+            reserved_name: pda(&[b"reserved", tld.as_ref(), &name_hash(name)]),
+            service: self.service_key(),
+            owner,
+            owner_role: None,
+            treasury: self.treasury,
+            tld_owner: self.tld_owner,
+            price_feed: None,
+            token_price: None,
+            payer_payment_account: None,
+            treasury_payment_account: None,
+            tld_owner_payment_account: None,
+            token_program: None,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        accounts: accounts::RegisterDomain,
+        name: &str,
+        years: u8,
+    ) -> std::result::Result<(), ProgramError> {
+        self.bank.process(
+            accounts,
+            instruction::RegisterDomain {
+                domain_name: name.to_string(),
+                tld_name: "pulse".to_string(),
+                years,
+            },
+        )
+    }
+
+    /// A funded wallet.
+    pub fn wallet(&mut self) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.bank.fund(wallet, 100 * LAMPORTS);
+        wallet
+    }
+
+    /// Stores a `pulse` domain registered now and expiring at `expires_at`.
+    pub fn add_domain(&mut self, name: &str, owner: Pubkey, expires_at: i64) -> Pubkey {
+        let key = self.domain_key(name);
+        self.bank.store(
+            &key,
+            &Domain {
+                name: name.to_string(),
+                tld: "pulse".to_string(),
+                owner,
+                registered_at: self.bank.now,
+                expires_at,
+                is_active: true,
+            },
+        );
+        key
+    }
+}
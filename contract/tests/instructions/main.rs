@@ -0,0 +1,15 @@
+//! Instruction handlers run end to end against an in-process bank, including
+//! their CPIs into the system and SPL token programs.
+
+#![allow(clippy::result_large_err)]
+
+mod account_constraints;
+mod handover;
+mod harness;
+mod migration;
+mod pausing;
+mod records;
+mod reserved_names;
+mod roles;
+mod token_payments;
+mod usd_pricing;
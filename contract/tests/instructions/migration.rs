@@ -0,0 +1,111 @@
+//! Upgrading service and TLD accounts created by the original deployment.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::Discriminator;
+use digitalpulse_tld::{
+    accounts, instruction, ErrorCode, Service, DEFAULT_GRACE_PERIOD, DEFAULT_MAX_PREPAID_YEARS,
+    ID, TLD,
+};
+
+use crate::harness::{assert_error, Fixture, LAMPORTS, START};
+
+/// Account data in an originally deployed layout: the current discriminator
+/// followed by only the fields that existed then.
+fn legacy_data<T: Discriminator>(fields: impl AnchorSerialize) -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    fields.serialize(&mut data).unwrap();
+    data
+}
+
+/// Replaces the fixture's service and `pulse` TLD with their original layouts.
+fn legacy_fixture() -> Fixture {
+    let mut f = Fixture::new();
+    let service = legacy_data::<Service>((f.authority, f.treasury, 12u64, 1u64));
+    f.bank.store_data(f.service_key(), ID, service);
+    let tld = legacy_data::<TLD>(("pulse".to_string(), LAMPORTS, f.tld_owner, START, true, 12u64));
+    f.bank.store_data(f.tld_key(), ID, tld);
+    f
+}
+
+fn migrate_service(f: &mut Fixture, signer: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::MigrateService {
+        service: f.service_key(),
+        authority: signer,
+        system_program: system_program::ID,
+    };
+    f.bank.process(accounts, instruction::MigrateService {})
+}
+
+fn migrate_tld(f: &mut Fixture, signer: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::MigrateTLD {
+        tld: f.tld_key(),
+        service: f.service_key(),
+        authority: signer,
+        system_program: system_program::ID,
+    };
+    f.bank.process(accounts, instruction::MigrateTld {})
+}
+
+#[test]
+fn migrated_service_keeps_its_state_and_gets_default_settings() {
+    let mut f = legacy_fixture();
+    let authority = f.authority;
+
+    migrate_service(&mut f, authority).unwrap();
+
+    let service = f.service();
+    assert_eq!(service.authority, f.authority);
+    assert_eq!(service.treasury, f.treasury);
+    assert_eq!(service.total_domains, 12);
+    assert_eq!(service.total_tlds, 1);
+    assert_eq!(service.grace_period, DEFAULT_GRACE_PERIOD);
+    assert_eq!(service.max_prepaid_years, DEFAULT_MAX_PREPAID_YEARS);
+    assert_eq!(service.pending_authority, None);
+    assert_eq!(service.paused, 0);
+}
+
+#[test]
+fn migrated_tld_renews_at_its_registration_price() {
+    let mut f = legacy_fixture();
+    let authority = f.authority;
+    migrate_service(&mut f, authority).unwrap();
+
+    migrate_tld(&mut f, authority).unwrap();
+
+    let tld = f.tld();
+    assert_eq!(tld.name, "pulse");
+    assert_eq!(tld.price, LAMPORTS);
+    assert_eq!(tld.renewal_price, LAMPORTS);
+    assert_eq!(tld.owner, f.tld_owner);
+    assert_eq!(tld.total_domains, 12);
+    assert!(tld.is_active);
+    assert_eq!(tld.owner_share_bps, 0);
+
+    // The migrated accounts work with the current instructions
+    let owner = f.wallet();
+    let accounts = f.register_accounts("alice", owner);
+    f.register(accounts, "alice", 1).unwrap();
+    assert_eq!(f.tld().total_domains, 13);
+}
+
+#[test]
+fn only_the_authority_can_migrate() {
+    let mut f = legacy_fixture();
+    let (authority, outsider) = (f.authority, f.wallet());
+
+    assert_error(migrate_service(&mut f, outsider), ErrorCode::Unauthorized);
+    migrate_service(&mut f, authority).unwrap();
+    assert_error(migrate_tld(&mut f, outsider), ErrorCode::Unauthorized);
+}
+
+#[test]
+fn accounts_are_migrated_only_once() {
+    let mut f = legacy_fixture();
+    let authority = f.authority;
+    migrate_service(&mut f, authority).unwrap();
+    migrate_tld(&mut f, authority).unwrap();
+
+    assert_error(migrate_service(&mut f, authority), ErrorCode::AccountAlreadyMigrated);
+    assert_error(migrate_tld(&mut f, authority), ErrorCode::AccountAlreadyMigrated);
+}
@@ -0,0 +1,254 @@
+//! Pause flags, each halting its own group of instructions.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use digitalpulse_tld::{
+    accounts, instruction, Auction, ErrorCode, RecordType, PAUSE_ALL, PAUSE_MARKETPLACE,
+    PAUSE_RECORDS, PAUSE_REGISTRATIONS, PAUSE_RENEWALS, PAUSE_TRANSFERS,
+};
+
+use crate::harness::{assert_error, pda, Fixture, LAMPORTS};
+
+const YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// A fixture with a guardian appointed by the authority.
+fn guarded_fixture() -> (Fixture, Pubkey) {
+    let mut f = Fixture::new();
+    let guardian = Pubkey::new_unique();
+    let accounts = accounts::UpdateGuardian {
+        service: f.service_key(),
+        authority: f.authority,
+    };
+    f.bank
+        .process(accounts, instruction::UpdateGuardian { guardian })
+        .unwrap();
+    (f, guardian)
+}
+
+fn set_paused(f: &mut Fixture, signer: Pubkey, paused: u8) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::SetPaused {
+        service: f.service_key(),
+        authority: signer,
+        authority_role: None,
+    };
+    f.bank.process(accounts, instruction::SetPaused { paused })
+}
+
+/// Runs `action` with `flag` paused, where it must fail, then with every
+/// other flag paused, where it must succeed.
+fn assert_paused_by(
+    flag: u8,
+    mut f: Fixture,
+    guardian: Pubkey,
+    action: impl Fn(&mut Fixture) -> std::result::Result<(), ProgramError>,
+) {
+    set_paused(&mut f, guardian, flag).unwrap();
+    assert_error(action(&mut f), ErrorCode::ServicePaused);
+
+    set_paused(&mut f, guardian, PAUSE_ALL & !flag).unwrap();
+    action(&mut f).unwrap();
+}
+
+#[test]
+fn registrations_flag_blocks_registration() {
+    let (mut f, guardian) = guarded_fixture();
+    let owner = f.wallet();
+
+    assert_paused_by(PAUSE_REGISTRATIONS, f, guardian, |f| {
+        let accounts = f.register_accounts("alice", owner);
+        f.register(accounts, "alice", 1)
+    });
+}
+
+#[test]
+fn renewals_flag_blocks_renewal() {
+    let (mut f, guardian) = guarded_fixture();
+    let owner = f.wallet();
+    let expires_at = f.bank.now + YEAR;
+    let domain = f.add_domain("alice", owner, expires_at);
+
+    assert_paused_by(PAUSE_RENEWALS, f, guardian, |f| {
+        let accounts = accounts::RenewDomain {
+            domain,
+            tld: f.tld_key(),
+            service: f.service_key(),
+            owner,
+            owner_role: None,
+            treasury: f.treasury,
+            tld_owner: f.tld_owner,
+            price_feed: None,
+            token_price: None,
+            payer_payment_account: None,
+            treasury_payment_account: None,
+            tld_owner_payment_account: None,
+            token_program: None,
+            system_program: system_program::ID,
+        };
+        f.bank.process(accounts, instruction::RenewDomain { years: 1 })
+    });
+}
+
+#[test]
+fn transfers_flag_blocks_gifting() {
+    let (mut f, guardian) = guarded_fixture();
+    let owner = f.wallet();
+    let expires_at = f.bank.now + YEAR;
+    let domain = f.add_domain("alice", owner, expires_at);
+
+    assert_paused_by(PAUSE_TRANSFERS, f, guardian, |f| {
+        let accounts = accounts::GiftDomain {
+            domain,
+            service: f.service_key(),
+            current_owner: owner,
+            current_owner_primary: pda(&[b"primary", owner.as_ref()]),
+            new_owner: Pubkey::new_unique(),
+        };
+        f.bank.process(accounts, instruction::GiftDomain {})
+    });
+}
+
+#[test]
+fn marketplace_flag_blocks_listing() {
+    let (mut f, guardian) = guarded_fixture();
+    let owner = f.wallet();
+    let expires_at = f.bank.now + YEAR;
+    let domain = f.add_domain("alice", owner, expires_at);
+
+    assert_paused_by(PAUSE_MARKETPLACE, f, guardian, |f| {
+        let accounts = accounts::ListDomain {
+            listing: pda(&[b"listing", domain.as_ref()]),
+            domain,
+            service: f.service_key(),
+            seller: owner,
+            system_program: system_program::ID,
+        };
+        f.bank.process(accounts, instruction::ListDomain { price: 1_000 })
+    });
+}
+
+/// An ended auction of `alice.pulse` with one escrowed bid. Unless `reclaimed`,
+/// the auction still holds the domain.
+fn ended_auction(f: &mut Fixture, reclaimed: bool) -> (accounts::SettleAuction, Pubkey) {
+    let seller = f.wallet();
+    let bidder = f.wallet();
+    let domain = f.domain_key("alice");
+    let auction = pda(&[b"auction", domain.as_ref()]);
+    let owner = if reclaimed { f.wallet() } else { auction };
+    f.add_domain("alice", owner, f.bank.now + YEAR);
+    f.bank.store(
+        &auction,
+        &Auction {
+            domain,
+            seller,
+            reserve_price: LAMPORTS,
+            min_increment: 1,
+            end_time: f.bank.now,
+            highest_bidder: Some(bidder),
+            highest_bid: LAMPORTS,
+            bump: 255,
+        },
+    );
+    f.bank.fund(auction, LAMPORTS);
+
+    let accounts = accounts::SettleAuction {
+        auction,
+        domain,
+        tld: f.tld_key(),
+        service: f.service_key(),
+        seller,
+        seller_primary: pda(&[b"primary", seller.as_ref()]),
+        recipient: bidder,
+        treasury: f.treasury,
+        tld_owner: f.tld_owner,
+    };
+    (accounts, bidder)
+}
+
+#[test]
+fn marketplace_flag_blocks_auction_sales() {
+    let (f, guardian) = guarded_fixture();
+
+    assert_paused_by(PAUSE_MARKETPLACE, f, guardian, |f| {
+        let (accounts, _) = ended_auction(f, false);
+        f.bank.process(accounts, instruction::SettleAuction {})
+    });
+}
+
+#[test]
+fn void_auctions_refund_the_bidder_while_the_marketplace_is_paused() {
+    let (mut f, guardian) = guarded_fixture();
+    set_paused(&mut f, guardian, PAUSE_ALL).unwrap();
+    let (accounts, bidder) = ended_auction(&mut f, true);
+    let auction = accounts.auction;
+    let balance = f.bank.lamports(&bidder);
+
+    f.bank.process(accounts, instruction::SettleAuction {}).unwrap();
+
+    assert_eq!(f.bank.lamports(&bidder), balance + LAMPORTS);
+    assert!(!f.bank.exists(&auction));
+}
+
+#[test]
+fn records_flag_blocks_record_updates() {
+    let (mut f, guardian) = guarded_fixture();
+    let owner = f.wallet();
+    let expires_at = f.bank.now + YEAR;
+    let domain = f.add_domain("alice", owner, expires_at);
+
+    assert_paused_by(PAUSE_RECORDS, f, guardian, |f| {
+        let accounts = accounts::SetRecord {
+            domain,
+            service: f.service_key(),
+            record: pda(&[b"record", domain.as_ref(), &[RecordType::A as u8]]),
+            owner,
+            system_program: system_program::ID,
+        };
+        f.bank.process(
+            accounts,
+            instruction::SetRecord {
+                record_type: RecordType::A,
+                data: vec![127, 0, 0, 1],
+            },
+        )
+    });
+}
+
+#[test]
+fn guardian_and_authority_can_pause_and_unpause() {
+    let (mut f, guardian) = guarded_fixture();
+    let authority = f.authority;
+
+    set_paused(&mut f, guardian, PAUSE_ALL).unwrap();
+    assert_eq!(f.service().paused, PAUSE_ALL);
+    set_paused(&mut f, authority, 0).unwrap();
+    assert_eq!(f.service().paused, 0);
+}
+
+#[test]
+fn only_the_guardian_or_authority_can_pause() {
+    let (mut f, guardian) = guarded_fixture();
+
+    assert_error(
+        set_paused(&mut f, Pubkey::new_unique(), PAUSE_ALL),
+        ErrorCode::Unauthorized,
+    );
+
+    // A replaced guardian loses the power to pause
+    let accounts = accounts::UpdateGuardian {
+        service: f.service_key(),
+        authority: f.authority,
+    };
+    f.bank
+        .process(accounts, instruction::UpdateGuardian { guardian: Pubkey::new_unique() })
+        .unwrap();
+    assert_error(set_paused(&mut f, guardian, PAUSE_ALL), ErrorCode::Unauthorized);
+    assert_eq!(f.service().paused, 0);
+}
+
+#[test]
+fn unknown_pause_flags_are_rejected() {
+    let (mut f, guardian) = guarded_fixture();
+
+    assert_error(set_paused(&mut f, guardian, 1 << 5), ErrorCode::InvalidPauseFlags);
+    assert_eq!(f.service().paused, 0);
+}
@@ -0,0 +1,87 @@
+//! Record updates and deletions, which only the owner of a live domain can make.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use digitalpulse_tld::{accounts, instruction, Domain, ErrorCode, RecordType};
+
+use crate::harness::{assert_error, pda, Fixture};
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn set_record(f: &mut Fixture, domain: Pubkey, owner: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::SetRecord {
+        domain,
+        service: f.service_key(),
+        record: pda(&[b"record", domain.as_ref(), &[RecordType::A as u8]]),
+        owner,
+        system_program: system_program::ID,
+    };
+    f.bank.process(
+        accounts,
+        instruction::SetRecord {
+            record_type: RecordType::A,
+            data: vec![127, 0, 0, 1],
+        },
+    )
+}
+
+fn delete_record(f: &mut Fixture, domain: Pubkey, owner: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::DeleteRecord {
+        domain,
+        service: f.service_key(),
+        record: pda(&[b"record", domain.as_ref(), &[RecordType::A as u8]]),
+        owner,
+    };
+    f.bank.process(accounts, instruction::DeleteRecord {})
+}
+
+/// Changes the stored `alice.pulse` domain.
+fn update_domain(f: &mut Fixture, update: impl FnOnce(&mut Domain)) {
+    let key = f.domain_key("alice");
+    let mut domain = f.domain("alice");
+    update(&mut domain);
+    f.bank.store(&key, &domain);
+}
+
+#[test]
+fn owner_sets_and_deletes_records() {
+    let mut f = Fixture::new();
+    let owner = f.wallet();
+    let domain = f.add_domain("alice", owner, f.bank.now + 365 * DAY);
+
+    set_record(&mut f, domain, owner).unwrap();
+    let outsider = f.wallet();
+    assert_error(delete_record(&mut f, domain, outsider), ErrorCode::NotDomainOwner);
+    delete_record(&mut f, domain, owner).unwrap();
+    assert!(!f.bank.exists(&pda(&[b"record", domain.as_ref(), &[RecordType::A as u8]])));
+}
+
+#[test]
+fn records_of_inactive_domains_are_frozen() {
+    let mut f = Fixture::new();
+    let owner = f.wallet();
+    let domain = f.add_domain("alice", owner, f.bank.now + 365 * DAY);
+    set_record(&mut f, domain, owner).unwrap();
+
+    update_domain(&mut f, |domain| domain.is_active = false);
+
+    assert_error(set_record(&mut f, domain, owner), ErrorCode::DomainNotActive);
+    assert_error(delete_record(&mut f, domain, owner), ErrorCode::DomainNotActive);
+}
+
+#[test]
+fn records_of_expired_domains_are_frozen() {
+    let mut f = Fixture::new();
+    let owner = f.wallet();
+    let domain = f.add_domain("alice", owner, f.bank.now + 365 * DAY);
+    set_record(&mut f, domain, owner).unwrap();
+
+    let expired_at = f.bank.now - DAY;
+    update_domain(&mut f, |domain| domain.expires_at = expired_at);
+    assert_error(set_record(&mut f, domain, owner), ErrorCode::DomainInGracePeriod);
+    assert_error(delete_record(&mut f, domain, owner), ErrorCode::DomainInGracePeriod);
+
+    f.bank.now += 60 * DAY;
+    assert_error(set_record(&mut f, domain, owner), ErrorCode::DomainExpired);
+    assert_error(delete_record(&mut f, domain, owner), ErrorCode::DomainExpired);
+}
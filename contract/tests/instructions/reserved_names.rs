@@ -0,0 +1,144 @@
+//! Assigning reserved names, including names reserved while registered.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use digitalpulse_tld::{accounts, instruction, name_hash, ErrorCode, PrimaryName, ReservedName};
+
+use crate::harness::{assert_error, pda, Fixture};
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn reserve(f: &mut Fixture, name: &str) -> Pubkey {
+    let tld = f.tld_key();
+    let key = pda(&[b"reserved", tld.as_ref(), &name_hash(name)]);
+    f.bank.store(
+        &key,
+        &ReservedName {
+            tld,
+            name: name.to_string(),
+            reserved_by: f.tld_owner,
+            created_at: f.bank.now,
+        },
+    );
+    key
+}
+
+fn assign(f: &mut Fixture, name: &str, owner: Pubkey) -> std::result::Result<(), ProgramError> {
+    let tld = f.tld_key();
+    let domain = f.domain_key(name);
+    let previous_owner = if f.bank.exists(&domain) {
+        f.domain(name).owner
+    } else {
+        Pubkey::default()
+    };
+    let accounts = accounts::AssignReservedName {
+        tld,
+        reserved_name: pda(&[b"reserved", tld.as_ref(), &name_hash(name)]),
+        domain,
+        service: f.service_key(),
+        previous_owner_primary: pda(&[b"primary", previous_owner.as_ref()]),
+        authority: f.tld_owner,
+        authority_role: None,
+        system_program: system_program::ID,
+    };
+    f.bank.process(
+        accounts,
+        instruction::AssignReservedName {
+            name: name.to_string(),
+            owner,
+            years: 1,
+        },
+    )
+}
+
+#[test]
+fn unregistered_reserved_name_is_assigned() {
+    let mut f = Fixture::new();
+    let reserved_name = reserve(&mut f, "alice");
+    let owner = Pubkey::new_unique();
+
+    assign(&mut f, "alice", owner).unwrap();
+
+    let domain = f.domain("alice");
+    assert_eq!(domain.owner, owner);
+    assert_eq!(domain.expires_at, f.bank.now + 365 * DAY);
+    assert!(!f.bank.exists(&reserved_name));
+    assert_eq!(f.service().total_domains, 1);
+    assert_eq!(f.tld().total_domains, 1);
+}
+
+#[test]
+fn name_reserved_while_registered_is_reassigned_once_expired() {
+    let mut f = Fixture::new();
+    let previous_owner = Pubkey::new_unique();
+    let expires_at = f.bank.now - f.service().grace_period - DAY;
+    let domain = f.add_domain("alice", previous_owner, expires_at);
+    let previous_primary = pda(&[b"primary", previous_owner.as_ref()]);
+    f.bank.store(
+        &previous_primary,
+        &PrimaryName {
+            owner: previous_owner,
+            domain,
+        },
+    );
+    let reserved_name = reserve(&mut f, "alice");
+    let owner = Pubkey::new_unique();
+
+    assign(&mut f, "alice", owner).unwrap();
+
+    let reassigned = f.domain("alice");
+    assert_eq!(reassigned.owner, owner);
+    assert_eq!(reassigned.registered_at, f.bank.now);
+    assert_eq!(reassigned.expires_at, f.bank.now + 365 * DAY);
+    assert!(!f.bank.exists(&reserved_name));
+    assert_eq!(
+        f.bank.load::<PrimaryName>(&previous_primary).domain,
+        Pubkey::default()
+    );
+    // The domain was already counted when first registered
+    assert_eq!(f.service().total_domains, 0);
+}
+
+#[test]
+fn name_reserved_while_registered_is_kept_until_the_grace_period_ends() {
+    let mut f = Fixture::new();
+    let previous_owner = Pubkey::new_unique();
+    let expires_at = f.bank.now - DAY;
+    f.add_domain("alice", previous_owner, expires_at);
+    let reserved_name = reserve(&mut f, "alice");
+
+    assert_error(
+        assign(&mut f, "alice", Pubkey::new_unique()),
+        ErrorCode::DomainNotExpired,
+    );
+    assert_eq!(f.domain("alice").owner, previous_owner);
+    assert!(f.bank.exists(&reserved_name));
+}
+
+#[test]
+fn reserved_names_are_not_assigned_under_a_deactivated_tld() {
+    let mut f = Fixture::new();
+    let reserved_name = reserve(&mut f, "alice");
+    let set_active = |f: &mut Fixture, active: bool| {
+        let accounts = accounts::UpdateTLDStatus {
+            tld: f.tld_key(),
+            service: f.service_key(),
+            authority: f.authority,
+            authority_role: None,
+        };
+        if active {
+            f.bank.process(accounts, instruction::ReactivateTld {})
+        } else {
+            f.bank.process(accounts, instruction::DeactivateTld {})
+        }
+        .unwrap();
+    };
+
+    set_active(&mut f, false);
+    assert_error(assign(&mut f, "alice", Pubkey::new_unique()), ErrorCode::TLDNotActive);
+    assert!(!f.bank.exists(&f.domain_key("alice")));
+    assert!(f.bank.exists(&reserved_name));
+
+    set_active(&mut f, true);
+    assign(&mut f, "alice", Pubkey::new_unique()).unwrap();
+}
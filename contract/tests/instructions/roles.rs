@@ -0,0 +1,161 @@
+//! TLD managers and what revoking their role takes away.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use digitalpulse_tld::{accounts, instruction, ErrorCode, RoleKind, TLD};
+
+use crate::harness::{assert_error, pda, Fixture, LAMPORTS};
+
+fn manager_role(manager: Pubkey) -> Pubkey {
+    pda(&[b"role", manager.as_ref(), &[RoleKind::TldManager as u8]])
+}
+
+fn grant_manager(f: &mut Fixture, manager: Pubkey) -> Pubkey {
+    let authority = f.authority;
+    let role = manager_role(manager);
+    let accounts = accounts::GrantRole {
+        role,
+        service: f.service_key(),
+        authority,
+        authority_role: None,
+        system_program: system_program::ID,
+    };
+    f.bank
+        .process(
+            accounts,
+            instruction::GrantRole {
+                holder: manager,
+                kind: RoleKind::TldManager,
+            },
+        )
+        .unwrap();
+    role
+}
+
+fn revoke(f: &mut Fixture, role: Pubkey) {
+    let authority = f.authority;
+    let accounts = accounts::RevokeRole {
+        role,
+        service: f.service_key(),
+        authority,
+        authority_role: None,
+    };
+    f.bank.process(accounts, instruction::RevokeRole {}).unwrap();
+}
+
+fn create_tld(
+    f: &mut Fixture,
+    name: &str,
+    signer: Pubkey,
+    role: Option<Pubkey>,
+) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::CreateTLD {
+        tld: pda(&[b"tld", name.as_bytes()]),
+        service: f.service_key(),
+        authority: signer,
+        authority_role: role,
+        system_program: system_program::ID,
+    };
+    f.bank.process(
+        accounts,
+        instruction::CreateTld {
+            name: name.to_string(),
+            price: LAMPORTS,
+            renewal_price: LAMPORTS,
+            allow_idna: false,
+        },
+    )
+}
+
+fn deactivate_tld(
+    f: &mut Fixture,
+    tld: Pubkey,
+    signer: Pubkey,
+    role: Option<Pubkey>,
+) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::UpdateTLDStatus {
+        tld,
+        service: f.service_key(),
+        authority: signer,
+        authority_role: role,
+    };
+    f.bank.process(accounts, instruction::DeactivateTld {})
+}
+
+fn transfer_tld(
+    f: &mut Fixture,
+    tld: Pubkey,
+    signer: Pubkey,
+    new_owner: Pubkey,
+) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::TransferTLDOwnership {
+        tld,
+        service: f.service_key(),
+        authority: signer,
+        authority_role: None,
+    };
+    f.bank
+        .process(accounts, instruction::TransferTldOwnership { new_owner })
+}
+
+fn update_tld_config(
+    f: &mut Fixture,
+    tld: Pubkey,
+    signer: Pubkey,
+) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::UpdateTLDConfig {
+        tld,
+        service: f.service_key(),
+        authority: signer,
+        authority_role: None,
+    };
+    f.bank
+        .process(accounts, instruction::UpdateTldConfig { allow_idna: true })
+}
+
+#[test]
+fn tlds_created_by_a_manager_belong_to_the_authority() {
+    let mut f = Fixture::new();
+    let manager = f.wallet();
+    let role = grant_manager(&mut f, manager);
+
+    create_tld(&mut f, "verse", manager, Some(role)).unwrap();
+
+    let tld = f.bank.load::<TLD>(&pda(&[b"tld", b"verse"]));
+    assert_eq!(tld.owner, f.authority);
+    assert!(tld.is_active);
+}
+
+#[test]
+fn revoked_manager_keeps_no_tld_control() {
+    let mut f = Fixture::new();
+    let manager = f.wallet();
+    let role = grant_manager(&mut f, manager);
+    create_tld(&mut f, "verse", manager, Some(role)).unwrap();
+    let tld = pda(&[b"tld", b"verse"]);
+
+    revoke(&mut f, role);
+    assert!(!f.bank.exists(&role));
+
+    assert_error(
+        create_tld(&mut f, "other", manager, None),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        deactivate_tld(&mut f, tld, manager, None),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        transfer_tld(&mut f, tld, manager, manager),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        update_tld_config(&mut f, tld, manager),
+        ErrorCode::Unauthorized,
+    );
+
+    let state = f.bank.load::<TLD>(&tld);
+    assert_eq!(state.owner, f.authority);
+    assert!(state.is_active);
+    assert!(!state.allow_idna);
+}
@@ -0,0 +1,305 @@
+//! Registration, renewal and offer payments in an SPL token accepted by the TLD.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use digitalpulse_tld::{accounts, instruction, ErrorCode, TokenPrice};
+
+use crate::harness::{assert_error, pda, Fixture};
+
+const YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// A fixture whose `pulse` TLD accepts a token at 100 per year (50 to renew),
+/// with the treasury's and TLD owner's associated token accounts for it.
+struct TokenFixture {
+    fixture: Fixture,
+    mint: Pubkey,
+    treasury_ata: Pubkey,
+    tld_owner_ata: Pubkey,
+}
+
+impl TokenFixture {
+    fn new() -> Self {
+        let mut fixture = Fixture::new();
+        let mint = Pubkey::new_unique();
+        fixture.bank.create_mint(mint);
+        let tld = fixture.tld_key();
+        fixture.bank.store(
+            &pda(&[b"token_price", tld.as_ref(), mint.as_ref()]),
+            &TokenPrice {
+                tld,
+                mint,
+                price: 100,
+                renewal_price: 50,
+            },
+        );
+
+        let treasury_ata = get_associated_token_address(&fixture.treasury, &mint);
+        let tld_owner_ata = get_associated_token_address(&fixture.tld_owner, &mint);
+        fixture.bank.create_token_account(treasury_ata, mint, fixture.treasury, 0);
+        fixture.bank.create_token_account(tld_owner_ata, mint, fixture.tld_owner, 0);
+
+        Self {
+            fixture,
+            mint,
+            treasury_ata,
+            tld_owner_ata,
+        }
+    }
+
+    fn token_price_key(&self) -> Pubkey {
+        let tld = self.fixture.tld_key();
+        pda(&[b"token_price", tld.as_ref(), self.mint.as_ref()])
+    }
+
+    /// A funded wallet and its token account holding `amount` of `mint`.
+    fn holder(&mut self, mint: Pubkey, amount: u64) -> (Pubkey, Pubkey) {
+        let wallet = self.fixture.wallet();
+        let token_account = Pubkey::new_unique();
+        self.fixture.bank.create_token_account(token_account, mint, wallet, amount);
+        (wallet, token_account)
+    }
+
+    fn register(
+        &mut self,
+        name: &str,
+        owner: Pubkey,
+        payer_payment_account: Pubkey,
+        years: u8,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = accounts::RegisterDomain {
+            token_price: Some(self.token_price_key()),
+            payer_payment_account: Some(payer_payment_account),
+            treasury_payment_account: Some(self.treasury_ata),
+            tld_owner_payment_account: Some(self.tld_owner_ata),
+            token_program: Some(anchor_spl::token::ID),
+            ..self.fixture.register_accounts(name, owner)
+        };
+        self.fixture.register(accounts, name, years)
+    }
+
+    fn offer_key(&self, domain: Pubkey, bidder: Pubkey) -> Pubkey {
+        pda(&[b"offer", domain.as_ref(), bidder.as_ref()])
+    }
+
+    /// Makes a token offer, creating the offer's escrow token account first.
+    fn make_offer(
+        &mut self,
+        domain: Pubkey,
+        bidder: Pubkey,
+        bidder_payment_account: Pubkey,
+        amount: u64,
+    ) -> std::result::Result<Pubkey, ProgramError> {
+        let offer = self.offer_key(domain, bidder);
+        let escrow = get_associated_token_address(&offer, &self.mint);
+        self.fixture.bank.create_token_account(escrow, self.mint, offer, 0);
+
+        let accounts = accounts::MakeOffer {
+            offer,
+            domain,
+            tld: self.fixture.tld_key(),
+            service: self.fixture.service_key(),
+            bidder,
+            token_price: Some(self.token_price_key()),
+            bidder_payment_account: Some(bidder_payment_account),
+            escrow_token_account: Some(escrow),
+            token_program: Some(anchor_spl::token::ID),
+            system_program: system_program::ID,
+        };
+        let expires_at = self.fixture.bank.now + 7 * 24 * 60 * 60;
+        self.fixture
+            .bank
+            .process(accounts, instruction::MakeOffer { amount, expires_at })?;
+        Ok(escrow)
+    }
+
+    fn accept_offer(
+        &mut self,
+        domain: Pubkey,
+        seller: Pubkey,
+        bidder: Pubkey,
+        seller_payment_account: Pubkey,
+    ) -> std::result::Result<(), ProgramError> {
+        let offer = self.offer_key(domain, bidder);
+        let f = &self.fixture;
+        let accounts = accounts::AcceptOffer {
+            offer,
+            domain,
+            tld: f.tld_key(),
+            service: f.service_key(),
+            seller,
+            seller_primary: pda(&[b"primary", seller.as_ref()]),
+            bidder,
+            treasury: f.treasury,
+            tld_owner: f.tld_owner,
+            escrow_token_account: Some(get_associated_token_address(&offer, &self.mint)),
+            seller_payment_account: Some(seller_payment_account),
+            treasury_payment_account: Some(self.treasury_ata),
+            tld_owner_payment_account: Some(self.tld_owner_ata),
+            token_program: Some(anchor_spl::token::ID),
+        };
+        self.fixture.bank.process(accounts, instruction::AcceptOffer {})
+    }
+}
+
+#[test]
+fn registration_fee_is_paid_in_tokens_and_split_with_the_tld_owner() {
+    let mut t = TokenFixture::new();
+    let (owner, payer_account) = t.holder(t.mint, 1_000);
+    let treasury_lamports = t.fixture.bank.lamports(&t.fixture.treasury);
+
+    t.register("alice", owner, payer_account, 2).unwrap();
+
+    // 2 years at 100, with the TLD owner's 10% share
+    let bank = &t.fixture.bank;
+    assert_eq!(bank.token_balance(&payer_account), 800);
+    assert_eq!(bank.token_balance(&t.treasury_ata), 180);
+    assert_eq!(bank.token_balance(&t.tld_owner_ata), 20);
+    assert_eq!(bank.lamports(&t.fixture.treasury), treasury_lamports);
+
+    let domain = t.fixture.domain("alice");
+    assert_eq!(domain.owner, owner);
+    assert_eq!(domain.expires_at, bank.now + 2 * YEAR);
+}
+
+#[test]
+fn renewal_fee_is_paid_at_the_token_renewal_price() {
+    let mut t = TokenFixture::new();
+    let (owner, payer_account) = t.holder(t.mint, 1_000);
+    t.register("alice", owner, payer_account, 1).unwrap();
+    let expires_at = t.fixture.domain("alice").expires_at;
+
+    let f = &t.fixture;
+    let accounts = accounts::RenewDomain {
+        domain: f.domain_key("alice"),
+        tld: f.tld_key(),
+        service: f.service_key(),
+        owner,
+        owner_role: None,
+        treasury: f.treasury,
+        tld_owner: f.tld_owner,
+        price_feed: None,
+        token_price: Some(t.token_price_key()),
+        payer_payment_account: Some(payer_account),
+        treasury_payment_account: Some(t.treasury_ata),
+        tld_owner_payment_account: Some(t.tld_owner_ata),
+        token_program: Some(anchor_spl::token::ID),
+        system_program: system_program::ID,
+    };
+    t.fixture
+        .bank
+        .process(accounts, instruction::RenewDomain { years: 1 })
+        .unwrap();
+
+    // 100 to register, then 50 to renew
+    let bank = &t.fixture.bank;
+    assert_eq!(bank.token_balance(&payer_account), 850);
+    assert_eq!(bank.token_balance(&t.treasury_ata), 90 + 45);
+    assert_eq!(bank.token_balance(&t.tld_owner_ata), 10 + 5);
+    assert_eq!(t.fixture.domain("alice").expires_at, expires_at + YEAR);
+}
+
+#[test]
+fn payment_account_of_another_mint_is_rejected() {
+    let mut t = TokenFixture::new();
+    let other_mint = Pubkey::new_unique();
+    t.fixture.bank.create_mint(other_mint);
+    let (owner, payer_account) = t.holder(other_mint, 1_000);
+
+    assert_error(
+        t.register("alice", owner, payer_account, 1),
+        ErrorCode::InvalidPaymentAccount,
+    );
+    assert!(!t.fixture.bank.exists(&t.fixture.domain_key("alice")));
+    assert_eq!(t.fixture.bank.token_balance(&payer_account), 1_000);
+}
+
+#[test]
+fn accepted_token_offer_pays_the_seller_and_royalties_from_escrow() {
+    let mut t = TokenFixture::new();
+    let (seller, seller_account) = t.holder(t.mint, 0);
+    let (bidder, bidder_account) = t.holder(t.mint, 5_000);
+    let expires_at = t.fixture.bank.now + YEAR;
+    let domain = t.fixture.add_domain("alice", seller, expires_at);
+
+    let escrow = t.make_offer(domain, bidder, bidder_account, 1_000).unwrap();
+    assert_eq!(t.fixture.bank.token_balance(&bidder_account), 4_000);
+    assert_eq!(t.fixture.bank.token_balance(&escrow), 1_000);
+
+    t.accept_offer(domain, seller, bidder, seller_account).unwrap();
+
+    // 5% royalty, of which the TLD owner takes 10%
+    let bank = &t.fixture.bank;
+    assert_eq!(bank.token_balance(&seller_account), 950);
+    assert_eq!(bank.token_balance(&t.treasury_ata), 45);
+    assert_eq!(bank.token_balance(&t.tld_owner_ata), 5);
+    assert!(!bank.exists(&escrow));
+    assert!(!bank.exists(&t.offer_key(domain, bidder)));
+    assert_eq!(t.fixture.domain("alice").owner, bidder);
+}
+
+#[test]
+fn token_offer_cannot_be_paid_into_an_account_of_another_mint() {
+    let mut t = TokenFixture::new();
+    let other_mint = Pubkey::new_unique();
+    t.fixture.bank.create_mint(other_mint);
+    let (seller, seller_account) = t.holder(other_mint, 0);
+    let (bidder, bidder_account) = t.holder(t.mint, 5_000);
+    let expires_at = t.fixture.bank.now + YEAR;
+    let domain = t.fixture.add_domain("alice", seller, expires_at);
+    let escrow = t.make_offer(domain, bidder, bidder_account, 1_000).unwrap();
+
+    assert_error(
+        t.accept_offer(domain, seller, bidder, seller_account),
+        ErrorCode::InvalidPaymentAccount,
+    );
+    assert_eq!(t.fixture.bank.token_balance(&escrow), 1_000);
+    assert_eq!(t.fixture.domain("alice").owner, seller);
+}
+
+#[test]
+fn offer_bid_from_an_account_of_another_mint_is_rejected() {
+    let mut t = TokenFixture::new();
+    let other_mint = Pubkey::new_unique();
+    t.fixture.bank.create_mint(other_mint);
+    let (bidder, bidder_account) = t.holder(other_mint, 5_000);
+    let expires_at = t.fixture.bank.now + YEAR;
+    let domain = t.fixture.add_domain("alice", Pubkey::new_unique(), expires_at);
+
+    assert_error(
+        t.make_offer(domain, bidder, bidder_account, 1_000).map(|_| ()),
+        ErrorCode::InvalidPaymentAccount,
+    );
+    assert!(!t.fixture.bank.exists(&t.offer_key(domain, bidder)));
+}
+
+#[test]
+fn cancelled_token_offer_refunds_the_bidder_from_escrow() {
+    let mut t = TokenFixture::new();
+    let (bidder, bidder_account) = t.holder(t.mint, 5_000);
+    let expires_at = t.fixture.bank.now + YEAR;
+    let domain = t.fixture.add_domain("alice", Pubkey::new_unique(), expires_at);
+    let escrow = t.make_offer(domain, bidder, bidder_account, 1_000).unwrap();
+    let bidder_lamports = t.fixture.bank.lamports(&bidder);
+    let offer = t.offer_key(domain, bidder);
+    let rent = t.fixture.bank.lamports(&offer) + t.fixture.bank.lamports(&escrow);
+
+    let accounts = accounts::CancelOffer {
+        offer,
+        bidder,
+        escrow_token_account: Some(escrow),
+        bidder_payment_account: Some(bidder_account),
+        token_program: Some(anchor_spl::token::ID),
+    };
+    t.fixture
+        .bank
+        .process(accounts, instruction::CancelOffer {})
+        .unwrap();
+
+    // The bid and the rent of both accounts return to the bidder
+    let bank = &t.fixture.bank;
+    assert_eq!(bank.token_balance(&bidder_account), 5_000);
+    assert_eq!(bank.lamports(&bidder), bidder_lamports + rent);
+    assert!(!bank.exists(&offer));
+    assert!(!bank.exists(&escrow));
+}
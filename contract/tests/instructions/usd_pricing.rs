@@ -0,0 +1,90 @@
+//! Fees of USD-priced TLDs, converted to lamports at a Pyth price account.
+
+use anchor_lang::prelude::*;
+use digitalpulse_tld::ErrorCode;
+
+use crate::harness::{assert_error, Fixture, START};
+
+/// A Pyth v2 price account, as the oracle's program would have written it.
+fn price_account(price: i64, conf: u64, expo: i32, publish_time: i64) -> Vec<u8> {
+    let mut data = vec![0; 240];
+    let mut write = |offset: usize, bytes: &[u8]| {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+    write(0, &0xa1b2c3d4u32.to_le_bytes()); // Magic
+    write(4, &2u32.to_le_bytes()); // Version
+    write(8, &3u32.to_le_bytes()); // Price account
+    write(12, &240u32.to_le_bytes());
+    write(20, &expo.to_le_bytes());
+    write(96, &publish_time.to_le_bytes());
+    write(208, &price.to_le_bytes());
+    write(216, &conf.to_le_bytes());
+    write(224, &1u32.to_le_bytes()); // Trading
+    data
+}
+
+/// A fixture whose `pulse` TLD costs $5.00 a year, with the service pointed
+/// at a SOL/USD price account published at `publish_time`.
+fn usd_fixture(price: i64, publish_time: i64) -> (Fixture, Pubkey) {
+    let mut f = Fixture::new();
+    let price_feed = Pubkey::new_unique();
+    f.bank
+        .store_data(price_feed, Pubkey::new_unique(), price_account(price, 0, -8, publish_time));
+
+    let mut service = f.service();
+    service.price_feed = price_feed;
+    f.set_service(&service);
+
+    let mut tld = f.tld();
+    tld.usd_pricing = true;
+    tld.price = 500;
+    tld.renewal_price = 500;
+    f.bank.store(&f.tld_key(), &tld);
+    (f, price_feed)
+}
+
+#[test]
+fn registration_fee_is_converted_at_the_oracle_price() {
+    // $100 per SOL, so $5.00 is 0.05 SOL
+    let (mut f, price_feed) = usd_fixture(10_000_000_000, START);
+    let owner = f.wallet();
+    let treasury = f.bank.lamports(&f.treasury);
+    let tld_owner = f.bank.lamports(&f.tld_owner);
+
+    let accounts = digitalpulse_tld::accounts::RegisterDomain {
+        price_feed: Some(price_feed),
+        ..f.register_accounts("alice", owner)
+    };
+    f.register(accounts, "alice", 2).unwrap();
+
+    assert_eq!(f.bank.lamports(&f.treasury), treasury + 90_000_000);
+    assert_eq!(f.bank.lamports(&f.tld_owner), tld_owner + 10_000_000);
+    assert_eq!(f.domain("alice").owner, owner);
+}
+
+#[test]
+fn stale_oracle_price_is_rejected() {
+    let (mut f, price_feed) = usd_fixture(10_000_000_000, START - 61);
+    let owner = f.wallet();
+
+    let accounts = digitalpulse_tld::accounts::RegisterDomain {
+        price_feed: Some(price_feed),
+        ..f.register_accounts("alice", owner)
+    };
+    assert_error(f.register(accounts, "alice", 1), ErrorCode::StaleOraclePrice);
+}
+
+#[test]
+fn price_account_other_than_the_service_feed_is_rejected() {
+    let (mut f, _) = usd_fixture(10_000_000_000, START);
+    let owner = f.wallet();
+    let spoofed_feed = Pubkey::new_unique();
+    f.bank
+        .store_data(spoofed_feed, Pubkey::new_unique(), price_account(1, 0, -8, START));
+
+    let accounts = digitalpulse_tld::accounts::RegisterDomain {
+        price_feed: Some(spoofed_feed),
+        ..f.register_accounts("alice", owner)
+    };
+    assert_error(f.register(accounts, "alice", 1), ErrorCode::InvalidPriceFeed);
+}
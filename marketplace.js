@@ -9,7 +9,6 @@ document.addEventListener('DOMContentLoaded', () => {
 });
 
 async function loadMarketplaceListings() {
-    // TODO: Replace with actual smart contract call
     allListings = await fetchMarketplaceListings();
     filteredListings = [...allListings];
     
@@ -17,62 +16,8 @@ async function loadMarketplaceListings() {
 }
 
 async function fetchMarketplaceListings() {
-    // TODO: Implement actual Solana program call
-    // This is demo data for now
-    
-    // Simulate API delay
-    await new Promise(resolve => setTimeout(resolve, 800));
-    
-    // Demo listings (replace with actual blockchain query)
-    const demoListings = [
-        {
-            name: 'crypto',
-            tld: '.verse',
-            price: 5.0,
-            seller: 'ABC123...XYZ789',
-            listedDate: new Date(Date.now() - 5 * 24 * 60 * 60 * 1000),
-            expiryDate: new Date(Date.now() + 320 * 24 * 60 * 60 * 1000),
-            nftMint: 'DEF456...'
-        },
-        {
-            name: 'web3',
-            tld: '.pulse',
-            price: 10.5,
-            seller: 'GHI789...MNO012',
-            listedDate: new Date(Date.now() - 2 * 24 * 60 * 60 * 1000),
-            expiryDate: new Date(Date.now() + 350 * 24 * 60 * 60 * 1000),
-            nftMint: 'GHI789...'
-        },
-        {
-            name: 'defi',
-            tld: '.cp',
-            price: 2.5,
-            seller: 'JKL012...PQR345',
-            listedDate: new Date(Date.now() - 1 * 24 * 60 * 60 * 1000),
-            expiryDate: new Date(Date.now() + 280 * 24 * 60 * 60 * 1000),
-            nftMint: 'JKL012...'
-        },
-        {
-            name: 'nft',
-            tld: '.pv',
-            price: 8.0,
-            seller: 'STU345...VWX678',
-            listedDate: new Date(Date.now() - 7 * 24 * 60 * 60 * 1000),
-            expiryDate: new Date(Date.now() + 300 * 24 * 60 * 60 * 1000),
-            nftMint: 'STU345...'
-        },
-        {
-            name: 'dao',
-            tld: '.pulse',
-            price: 0.8,
-            seller: 'YZA678...BCD901',
-            listedDate: new Date(Date.now() - 3 * 24 * 60 * 60 * 1000),
-            expiryDate: new Date(Date.now() + 340 * 24 * 60 * 60 * 1000),
-            nftMint: 'YZA678...'
-        }
-    ];
-    
-    return demoListings;
+    // Listing PDAs escrow the domain NFT until bought or cancelled
+    return blockchainService.getListings();
 }
 
 function applyFilters() {
@@ -189,13 +134,24 @@ function createListingCard(listing) {
 }
 
 async function buyDomain(listing) {
-    if (!walletAddress) {
+    // Listings are bought with the buyer's Phantom wallet, which signs the purchase
+    if (!window.solana || !window.solana.isPhantom) {
+        alert('Please install Phantom wallet to buy domains!');
+        return;
+    }
+    
+    let buyer;
+    try {
+        const response = await window.solana.connect();
+        buyer = response.publicKey.toString();
+        await blockchainService.connectWallet(window.solana);
+    } catch (error) {
         alert('Please connect your wallet first!');
         return;
     }
     
     // Check if buyer is the seller
-    if (listing.seller === walletAddress) {
+    if (listing.seller === buyer) {
         alert('You cannot buy your own domain!');
         return;
     }
@@ -208,30 +164,27 @@ async function buyDomain(listing) {
         `Total Price: ${listing.price} SOL\n` +
         `Marketplace Fee (${MARKETPLACE_FEE_PERCENT}%): ${marketplaceFee.toFixed(3)} SOL\n` +
         `Seller Receives: ${sellerReceives.toFixed(3)} SOL\n\n` +
-        `From: ${walletAddress.slice(0, 4)}...${walletAddress.slice(-4)}\n` +
+        `From: ${buyer.slice(0, 4)}...${buyer.slice(-4)}\n` +
         `To: ${listing.seller.slice(0, 4)}...${listing.seller.slice(-4)}`
     );
     
     if (confirmed) {
-        // TODO: Implement actual Solana marketplace purchase transaction
-        // This should:
-        // 1. Transfer SOL from buyer to seller (minus marketplace fee)
-        // 2. Transfer marketplace fee to platform wallet
-        // 3. Transfer NFT ownership from seller to buyer
-        // 4. Remove listing from marketplace
-        
-        alert(
-            'Purchase transaction initiated!\n\n' +
-            'Smart contract integration in progress.\n\n' +
-            'Transaction will:\n' +
-            `- Transfer ${listing.price} SOL from your wallet\n` +
-            `- Send ${sellerReceives.toFixed(3)} SOL to seller\n` +
-            `- Send ${marketplaceFee.toFixed(3)} SOL marketplace fee\n` +
-            `- Transfer ${listing.name}${listing.tld} NFT to you`
-        );
-        
-        // After successful purchase, reload marketplace
-        // await loadMarketplaceListings();
+        // buy_listing_nft atomically pays the seller, treasury and TLD owner,
+        // releases the escrowed NFT to the buyer and closes the listing. It
+        // fails if the listing price was raised above the price shown here.
+        try {
+            const signature = await blockchainService.buyListing(listing);
+            
+            alert(
+                `You now own ${listing.name}${listing.tld}!\n\n` +
+                `Transaction: ${signature}`
+            );
+            
+            await loadMarketplaceListings();
+        } catch (error) {
+            console.error('Purchase failed:', error);
+            alert(`Purchase failed: ${error.message || error}`);
+        }
     }
 }
 
@@ -1,14 +1,20 @@
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount, mint_to, MintTo},
+    associated_token::{get_associated_token_address, AssociatedToken},
+    token::{Mint, Token, TokenAccount, mint_to, set_authority, MintTo, SetAuthority},
+    token::spl_token::instruction::AuthorityType,
     metadata::{
         create_metadata_accounts_v3,
         CreateMetadataAccountsV3,
         Metadata as MetadataProgram,
-        mpl_token_metadata::types::{DataV2, Creator, CollectionDetails},
     },
 };
+use mpl_token_metadata::state::{Creator, DataV2};
+
+mod oracle;
+mod validation;
 
 declare_id!("2skfTcCdVRkrVdhrvQ9JTWyccgt9jmuPP2S7iT8RSEcJ");
 
@@ -16,11 +22,41 @@ declare_id!("2skfTcCdVRkrVdhrvQ9JTWyccgt9jmuPP2S7iT8RSEcJ");
 pub const INITIAL_AUTHORITY_WALLET: &str = "GJUdwrWeFVBZkwVSwjzfnhJMPyGo3hUeQ7ZxmonaWMdH";
 pub const INITIAL_TREASURY_WALLET: &str = "ETGuhexB39NqELD9RFkqtCELPsAB7KsNLFUbQxcLLzpe";
 
-// Fee structure
-pub const REGISTRATION_FEE: u64 = 250_000_000; // 0.25 SOL
-pub const RENEWAL_FEE: u64 = 150_000_000; // 0.15 SOL
+// Fee structure (registration and renewal prices are set per TLD)
+pub const MAX_REVENUE_SHARE_BPS: u16 = 10_000; // TLD owner share of fees and royalties
 pub const ROYALTY_PERCENTAGE: u16 = 500; // 5% in basis points (500/10000)
 
+// Length-tiered pricing
+pub const MAX_PRICE_TIERS: usize = 8;
+
+// Expiry
+pub const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+pub const DEFAULT_GRACE_PERIOD: i64 = 30 * 24 * 60 * 60; // 30 days
+pub const DEFAULT_MAX_PREPAID_YEARS: u8 = 10;
+
+// Records
+pub const MAX_RECORD_DATA_LEN: usize = 256;
+
+// Auctions
+pub const MAX_AUCTION_DURATION: i64 = 30 * 24 * 60 * 60; // 30 days
+pub const AUCTION_EXTENSION_WINDOW: i64 = 10 * 60; // Bids this close to the end extend it
+
+// TLD applications
+pub const TLD_APPLICATION_DEPOSIT: u64 = 5_000_000_000; // 5 SOL, kept by the treasury on approval
+
+// Price oracle
+pub const DEFAULT_MAX_PRICE_AGE: i64 = 60; // seconds
+pub const DEFAULT_MAX_PRICE_CONFIDENCE_BPS: u16 = 200; // 2%
+
+// Pause flags, each halting one group of instructions
+pub const PAUSE_REGISTRATIONS: u8 = 1 << 0;
+pub const PAUSE_RENEWALS: u8 = 1 << 1;
+pub const PAUSE_TRANSFERS: u8 = 1 << 2;
+pub const PAUSE_MARKETPLACE: u8 = 1 << 3; // Cancellations and refunds stay available
+pub const PAUSE_RECORDS: u8 = 1 << 4;
+pub const PAUSE_ALL: u8 =
+    PAUSE_REGISTRATIONS | PAUSE_RENEWALS | PAUSE_TRANSFERS | PAUSE_MARKETPLACE | PAUSE_RECORDS;
+
 #[program]
 pub mod digitalpulse_tld {
     use super::*;
@@ -31,6 +67,15 @@ pub mod digitalpulse_tld {
         service.treasury = INITIAL_TREASURY_WALLET.parse().unwrap();
         service.total_domains = 0;
         service.total_tlds = 0;
+        service.grace_period = DEFAULT_GRACE_PERIOD;
+        service.max_prepaid_years = DEFAULT_MAX_PREPAID_YEARS;
+        service.price_feed = Pubkey::default();
+        service.max_price_age = DEFAULT_MAX_PRICE_AGE;
+        service.max_price_confidence_bps = DEFAULT_MAX_PRICE_CONFIDENCE_BPS;
+        service.pending_authority = None;
+        service.pending_treasury = None;
+        service.guardian = Pubkey::default();
+        service.paused = 0;
         
         msg!("DigitalPulse TLD Service initialized with NFT support");
         msg!("Authority: {}", service.authority);
@@ -39,22 +84,366 @@ pub mod digitalpulse_tld {
         Ok(())
     }
 
-    pub fn create_tld(ctx: Context<CreateTLD>, name: String, price: u64) -> Result<()> {
-        require!(name.len() <= 10, ErrorCode::TLDNameTooLong);
+    pub fn create_tld(
+        ctx: Context<CreateTLD>,
+        name: String,
+        price: u64,
+        renewal_price: u64,
+        allow_idna: bool,
+    ) -> Result<()> {
+        let name = validation::normalize(&name);
+        validation::validate_tld_name(&name)?;
+        require!(price > 0, ErrorCode::InvalidPrice);
+        require!(renewal_price > 0, ErrorCode::InvalidPrice);
+        
+        let service = &mut ctx.accounts.service;
+        
+        // Verify authority or TLD manager role; anyone else applies for a TLD
+        service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        
+        // Managers create TLDs on the service's behalf, so revoking their role
+        // leaves them no control over the TLDs they created
+        ctx.accounts.tld.initialize(
+            name.clone(),
+            price,
+            renewal_price,
+            allow_idna,
+            service.authority,
+            Clock::get()?.unix_timestamp,
+        );
+        service.total_tlds += 1;
+        
+        msg!("TLD created: .{} with price: {} lamports", name, price);
+        msg!("Renewal price: {} lamports", renewal_price);
+        Ok(())
+    }
+
+    pub fn submit_tld_application(
+        ctx: Context<SubmitTldApplication>,
+        name: String,
+        price: u64,
+        renewal_price: u64,
+        allow_idna: bool,
+    ) -> Result<()> {
+        let name = validation::normalize(&name);
+        validation::validate_tld_name(&name)?;
+        require!(price > 0, ErrorCode::InvalidPrice);
+        require!(renewal_price > 0, ErrorCode::InvalidPrice);
+        require!(ctx.accounts.tld.data_is_empty(), ErrorCode::TLDAlreadyExists);
+        
+        // Hold the deposit in the application account until it is decided
+        let transfer_instruction = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.applicant.to_account_info(),
+            to: ctx.accounts.application.to_account_info(),
+        };
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                transfer_instruction,
+            ),
+            TLD_APPLICATION_DEPOSIT,
+        )?;
+        
+        let application = &mut ctx.accounts.application;
+        application.applicant = ctx.accounts.applicant.key();
+        application.name = name.clone();
+        application.price = price;
+        application.renewal_price = renewal_price;
+        application.allow_idna = allow_idna;
+        application.deposit = TLD_APPLICATION_DEPOSIT;
+        application.created_at = Clock::get()?.unix_timestamp;
+        
+        msg!("TLD application submitted: .{} by {}", name, application.applicant);
+        
+        emit!(TldApplicationSubmitted {
+            application: application.key(),
+            applicant: application.applicant,
+            name,
+            deposit: application.deposit,
+        });
+        
+        Ok(())
+    }
+
+    pub fn approve_tld_application(ctx: Context<ApproveTldApplication>) -> Result<()> {
+        let application = &ctx.accounts.application;
+        let service = &mut ctx.accounts.service;
+        
+        // Verify authority or TLD manager role
+        service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        
+        // The deposit goes to the treasury; the application rent returns to the applicant on close
+        **application.to_account_info().try_borrow_mut_lamports()? -= application.deposit;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += application.deposit;
+        
+        ctx.accounts.tld.initialize(
+            application.name.clone(),
+            application.price,
+            application.renewal_price,
+            application.allow_idna,
+            application.applicant,
+            Clock::get()?.unix_timestamp,
+        );
+        service.total_tlds += 1;
+        
+        msg!("TLD application approved: .{}", application.name);
+        msg!("TLD owner: {}", application.applicant);
+        
+        emit!(TldApplicationApproved {
+            application: application.key(),
+            tld: ctx.accounts.tld.key(),
+            owner: application.applicant,
+        });
+        
+        Ok(())
+    }
+
+    pub fn reject_tld_application(ctx: Context<RejectTldApplication>) -> Result<()> {
+        let application = &ctx.accounts.application;
+        
+        // Verify authority or TLD manager role
+        ctx.accounts.service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        
+        // Closing the application refunds the deposit and rent to the applicant
+        msg!("TLD application rejected: .{}", application.name);
+        
+        emit!(TldApplicationRejected {
+            application: application.key(),
+            applicant: application.applicant,
+        });
+        
+        Ok(())
+    }
+
+    pub fn withdraw_tld_application(ctx: Context<WithdrawTldApplication>) -> Result<()> {
+        let application = &ctx.accounts.application;
+        
+        // Closing the application refunds the deposit and rent to the applicant
+        msg!("TLD application withdrawn: .{}", application.name);
+        
+        emit!(TldApplicationWithdrawn {
+            application: application.key(),
+            applicant: application.applicant,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_tld_pricing(
+        ctx: Context<UpdateTLDPricing>,
+        price: u64,
+        renewal_price: u64,
+        usd_pricing: bool,
+    ) -> Result<()> {
         require!(price > 0, ErrorCode::InvalidPrice);
+        require!(renewal_price > 0, ErrorCode::InvalidPrice);
+        
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
         
         let tld = &mut ctx.accounts.tld;
-        tld.name = name.clone();
+        
+        let old_price = tld.price;
+        let old_renewal_price = tld.renewal_price;
         tld.price = price;
-        tld.owner = ctx.accounts.authority.key();
-        tld.created_at = Clock::get()?.unix_timestamp;
+        tld.renewal_price = renewal_price;
+        tld.usd_pricing = usd_pricing;
+        
+        let unit = if usd_pricing { "USD cents" } else { "lamports" };
+        msg!("TLD pricing updated: .{}", tld.name);
+        msg!("Registration price: {} -> {} {}", old_price, price, unit);
+        msg!("Renewal price: {} -> {} {}", old_renewal_price, renewal_price, unit);
+        
+        emit!(TLDPricingUpdated {
+            tld: tld.key(),
+            old_price,
+            new_price: price,
+            old_renewal_price,
+            new_renewal_price: renewal_price,
+            usd_pricing,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_release_auction(
+        ctx: Context<UpdateReleaseAuction>,
+        start_price: u64,
+        period: i64,
+    ) -> Result<()> {
+        require!(period >= 0, ErrorCode::InvalidReleasePeriod);
+        
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
+        
+        let tld = &mut ctx.accounts.tld;
+        
+        tld.release_start_price = start_price;
+        tld.release_period = period;
+        
+        msg!("Release auction updated: .{}", tld.name);
+        msg!("Start price: {}, decay period: {} seconds", start_price, period);
+        
+        emit!(ReleaseAuctionUpdated {
+            tld: tld.key(),
+            start_price,
+            period,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_tld_revenue_share(
+        ctx: Context<UpdateTLDRevenueShare>,
+        owner_share_bps: u16,
+    ) -> Result<()> {
+        require!(
+            owner_share_bps <= MAX_REVENUE_SHARE_BPS,
+            ErrorCode::InvalidRevenueShare
+        );
+        
+        let tld = &mut ctx.accounts.tld;
+        
+        // Verify authority or admin role
+        ctx.accounts.service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::Admin,
+        )?;
+        
+        let old_owner_share_bps = tld.owner_share_bps;
+        tld.owner_share_bps = owner_share_bps;
+        
+        msg!("TLD revenue share updated: .{}", tld.name);
+        msg!("TLD owner share: {} -> {} bps", old_owner_share_bps, owner_share_bps);
+        
+        emit!(TLDRevenueShareUpdated {
+            tld: tld.key(),
+            old_owner_share_bps,
+            new_owner_share_bps: owner_share_bps,
+        });
+        
+        Ok(())
+    }
+
+    pub fn deactivate_tld(ctx: Context<UpdateTLDStatus>) -> Result<()> {
+        let tld = &mut ctx.accounts.tld;
+        
+        // Verify authority or TLD manager role
+        ctx.accounts.service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        require!(tld.is_active, ErrorCode::TLDNotActive);
+        
+        // New registrations stop; existing domains can still be renewed, transferred and sold
+        tld.is_active = false;
+        
+        msg!("TLD deactivated: .{}", tld.name);
+        
+        emit!(TLDDeactivated {
+            tld: tld.key(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn reactivate_tld(ctx: Context<UpdateTLDStatus>) -> Result<()> {
+        let tld = &mut ctx.accounts.tld;
+        
+        // Verify authority or TLD manager role
+        ctx.accounts.service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        require!(!tld.is_active, ErrorCode::TLDAlreadyActive);
+        
         tld.is_active = true;
-        tld.total_domains = 0;
         
-        let service = &mut ctx.accounts.service;
-        service.total_tlds += 1;
+        msg!("TLD reactivated: .{}", tld.name);
+        
+        emit!(TLDReactivated {
+            tld: tld.key(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn transfer_tld_ownership(
+        ctx: Context<TransferTLDOwnership>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        // The TLD owner can hand the TLD on, and the authority can reassign it
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        
+        let tld = &mut ctx.accounts.tld;
+        let old_owner = tld.owner;
+        tld.owner = new_owner;
+        
+        msg!("TLD ownership transferred: .{}", tld.name);
+        msg!("Old owner: {}", old_owner);
+        msg!("New owner: {}", new_owner);
+        
+        emit!(TLDOwnershipTransferred {
+            tld: tld.key(),
+            old_owner,
+            new_owner,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_tld_config(ctx: Context<UpdateTLDConfig>, allow_idna: bool) -> Result<()> {
+        // TLD owners manage their own TLD's settings
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::TldManager,
+        )?;
+        
+        // Only affects new registrations; existing punycode domains are kept
+        let tld = &mut ctx.accounts.tld;
+        tld.allow_idna = allow_idna;
+        
+        msg!("TLD config updated: .{}", tld.name);
+        msg!("IDNA names allowed: {}", allow_idna);
+        
+        emit!(TLDConfigUpdated {
+            tld: tld.key(),
+            allow_idna,
+        });
         
-        msg!("TLD created: .{} with price: {} lamports", name, price);
         Ok(())
     }
 
@@ -62,9 +451,9 @@ pub mod digitalpulse_tld {
         ctx: Context<RegisterDomainNFT>,
         domain_name: String,
         tld_name: String,
+        years: u8,
     ) -> Result<()> {
-        require!(domain_name.len() <= 63, ErrorCode::DomainNameTooLong);
-        require!(domain_name.len() >= 1, ErrorCode::DomainNameTooShort);
+        ctx.accounts.service.check_not_paused(PAUSE_REGISTRATIONS)?;
         
         let domain = &mut ctx.accounts.domain;
         let tld = &mut ctx.accounts.tld;
@@ -74,31 +463,95 @@ pub mod digitalpulse_tld {
         // Validate TLD is active
         require!(tld.is_active, ErrorCode::TLDNotActive);
         
-        // Check if owner is authority
-        let is_authority = ctx.accounts.owner.key().to_string() == service.authority.to_string();
+        // Names are stored and seeded in normalized form
+        let domain_name = validation::normalize(&domain_name);
+        let tld_name = validation::normalize(&tld_name);
+        validation::validate_domain_name(&domain_name, tld.allow_idna)?;
         
-        if !is_authority {
-            // Regular users pay registration fee
-            require!(
-                ctx.accounts.owner.lamports() >= REGISTRATION_FEE,
-                ErrorCode::InsufficientFunds
-            );
-            
-            // Transfer registration fee to treasury
-            let transfer_instruction = anchor_lang::system_program::Transfer {
-                from: ctx.accounts.owner.to_account_info(),
-                to: ctx.accounts.treasury.to_account_info(),
-            };
-            
-            anchor_lang::system_program::transfer(
-                CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    transfer_instruction,
-                ),
-                REGISTRATION_FEE,
-            )?;
-            
-            msg!("Registration fee paid: {} SOL", REGISTRATION_FEE as f64 / 1_000_000_000.0);
+        // Reserved names can only be assigned by the TLD's managers
+        require!(ctx.accounts.reserved_name.data_is_empty(), ErrorCode::NameIsReserved);
+        
+        let expires_at = extend_expiry(
+            clock.unix_timestamp,
+            clock.unix_timestamp,
+            years,
+            service.max_prepaid_years,
+        )?;
+        
+        // Premium and length-tiered prices take precedence over the TLD base price
+        let (price_per_year, premium_released_at) = registration_price(
+            tld.price,
+            &domain_name,
+            &ctx.accounts.pricing_schedule.to_account_info(),
+            &ctx.accounts.premium_name.to_account_info(),
+        )?;
+        
+        // Newly available premium names are sold through a declining-price auction
+        let release_premium = match premium_released_at {
+            Some(released_at) => {
+                release_auction_premium(tld, price_per_year, released_at, clock.unix_timestamp)?
+            }
+            None => 0,
+        };
+        
+        // The authority and support operators are not charged
+        let is_operator = service.has_role(
+            ctx.accounts.owner.key(),
+            &ctx.accounts.owner_role,
+            RoleKind::Operator,
+        );
+        
+        if !is_operator {
+            // Regular users pay the name's registration price for each year, plus any release premium
+            let registration_fee = price_per_year
+                .checked_mul(years as u64)
+                .and_then(|fee| fee.checked_add(release_premium))
+                .ok_or(ErrorCode::MathOverflow)?;
+            match TokenPayment::load(
+                &ctx.accounts.token_price,
+                &ctx.accounts.payer_payment_account,
+                &ctx.accounts.treasury_payment_account,
+                &ctx.accounts.tld_owner_payment_account,
+                Some(ctx.accounts.token_program.to_account_info()),
+                tld,
+                ctx.accounts.owner.key(),
+                service.treasury,
+            )? {
+                Some(payment) => {
+                    // Token prices scale premium and length-tiered prices by the same ratio
+                    let token_fee = scale_price(registration_fee, tld.price, payment.token_price.price)?;
+                    payment.pay_revenue(&ctx.accounts.owner.to_account_info(), token_fee)?;
+                    
+                    msg!("Registration fee paid: {} tokens of {}", token_fee, payment.token_price.mint);
+                }
+                None => {
+                    // USD-priced TLDs are converted to lamports at the oracle price
+                    let registration_fee = lamports_for_fee(
+                        registration_fee,
+                        tld,
+                        service,
+                        &ctx.accounts.price_feed,
+                        clock.unix_timestamp,
+                    )?;
+                    
+                    require!(
+                        ctx.accounts.owner.lamports() >= registration_fee,
+                        ErrorCode::InsufficientFunds
+                    );
+                    
+                    // Split the registration fee between the treasury and the TLD owner
+                    pay_revenue(
+                        &ctx.accounts.owner.to_account_info(),
+                        &ctx.accounts.treasury,
+                        &ctx.accounts.tld_owner,
+                        &ctx.accounts.system_program.to_account_info(),
+                        tld,
+                        registration_fee,
+                    )?;
+                    
+                    msg!("Registration fee paid: {} SOL", registration_fee as f64 / 1_000_000_000.0);
+                }
+            }
         } else {
             msg!("Authority wallet - FREE registration");
         }
@@ -108,131 +561,213 @@ pub mod digitalpulse_tld {
         domain.tld = tld_name.clone();
         domain.owner = ctx.accounts.owner.key();
         domain.registered_at = clock.unix_timestamp;
-        domain.expires_at = clock.unix_timestamp + (365 * 24 * 60 * 60); // 1 year
+        domain.expires_at = expires_at;
         domain.is_active = true;
         domain.mint = ctx.accounts.mint.key();
         
-        // Mint NFT (1 token)
-        let cpi_accounts = MintTo {
-            mint: ctx.accounts.mint.to_account_info(),
-            to: ctx.accounts.token_account.to_account_info(),
-            authority: ctx.accounts.owner.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        mint_to(cpi_ctx, 1)?;
-        
-        // Create NFT metadata
+        // Mint NFT (1 token) with its metadata
         let full_domain = format!("{}.{}", domain_name, tld_name);
-        let metadata_uri = format!("https://digitalpulse.pv/metadata/{}.json", full_domain);
-        
-        let creators = vec![
-            Creator {
-                address: service.treasury,
-                verified: false,
-                share: 100,
+        mint_domain_nft(
+            DomainNftAccounts {
+                mint: ctx.accounts.mint.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                owner: ctx.accounts.owner.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                metadata_program: ctx.accounts.metadata_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
             },
-        ];
-        
-        let data_v2 = DataV2 {
-            name: full_domain.clone(),
-            symbol: String::from("DPULSE"),
-            uri: metadata_uri,
-            seller_fee_basis_points: ROYALTY_PERCENTAGE,
-            creators: Some(creators),
-            collection: None,
-            uses: None,
-        };
-        
-        let cpi_accounts = CreateMetadataAccountsV3 {
-            metadata: ctx.accounts.metadata.to_account_info(),
-            mint: ctx.accounts.mint.to_account_info(),
-            mint_authority: ctx.accounts.owner.to_account_info(),
-            update_authority: ctx.accounts.owner.to_account_info(),
-            payer: ctx.accounts.owner.to_account_info(),
-            system_program: ctx.accounts.system_program.to_account_info(),
-            rent: ctx.accounts.rent.to_account_info(),
-        };
-        
-        let cpi_program = ctx.accounts.metadata_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        
-        create_metadata_accounts_v3(
-            cpi_ctx,
-            data_v2,
-            true,  // is_mutable
-            true,  // update_authority_is_signer
-            None,  // collection_details
+            &full_domain,
+            service.treasury,
         )?;
         
         tld.total_domains += 1;
         service.total_domains += 1;
         
-        msg!("Domain registered as NFT: {}.{}", domain_name, tld_name);
+        msg!("Domain registered as NFT: {}.{} for {} year(s)", domain_name, tld_name, years);
         msg!("NFT Mint: {}", ctx.accounts.mint.key());
         Ok(())
     }
 
-    pub fn renew_domain(ctx: Context<RenewDomain>) -> Result<()> {
+    pub fn set_token_price(
+        ctx: Context<SetTokenPrice>,
+        price: u64,
+        renewal_price: u64,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        require!(renewal_price > 0, ErrorCode::InvalidPrice);
+        
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
+        
+        let token_price = &mut ctx.accounts.token_price;
+        token_price.tld = ctx.accounts.tld.key();
+        token_price.mint = ctx.accounts.mint.key();
+        token_price.price = price;
+        token_price.renewal_price = renewal_price;
+        
+        msg!("Token accepted for .{}: {}", ctx.accounts.tld.name, token_price.mint);
+        msg!("Registration price: {}, renewal price: {}", price, renewal_price);
+        
+        emit!(TokenPriceUpdated {
+            tld: token_price.tld,
+            mint: token_price.mint,
+            price,
+            renewal_price,
+        });
+        
+        Ok(())
+    }
+
+    pub fn remove_token_price(ctx: Context<RemoveTokenPrice>) -> Result<()> {
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
+        
+        let token_price = &ctx.accounts.token_price;
+        
+        msg!("Token no longer accepted for .{}: {}", ctx.accounts.tld.name, token_price.mint);
+        
+        emit!(TokenPriceUpdated {
+            tld: token_price.tld,
+            mint: token_price.mint,
+            price: 0,
+            renewal_price: 0,
+        });
+        
+        Ok(())
+    }
+
+    pub fn renew_domain(ctx: Context<RenewDomain>, years: u8) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_RENEWALS)?;
+        
         let domain = &mut ctx.accounts.domain;
         let service = &ctx.accounts.service;
         let clock = Clock::get()?;
         
         require!(domain.is_active, ErrorCode::DomainNotActive);
         
+        // Expired domains can still be renewed until the grace period ends, even
+        // under a deactivated TLD
+        domain.check_renewable(clock.unix_timestamp, service.grace_period)?;
+        
         // Verify domain ownership
         require!(
             domain.owner == ctx.accounts.owner.key(),
             ErrorCode::NotDomainOwner
         );
         
-        // Check if owner is authority
-        let is_authority = ctx.accounts.owner.key().to_string() == service.authority.to_string();
+        // Extend from the current expiry so renewing early keeps the remaining time
+        let new_expires_at = extend_expiry(
+            clock.unix_timestamp.max(domain.expires_at),
+            clock.unix_timestamp,
+            years,
+            service.max_prepaid_years,
+        )?;
         
-        if !is_authority {
-            // Regular users pay renewal fee
-            require!(
-                ctx.accounts.owner.lamports() >= RENEWAL_FEE,
-                ErrorCode::InsufficientFunds
-            );
-            
-            // Transfer renewal fee to treasury
-            let transfer_instruction = anchor_lang::system_program::Transfer {
-                from: ctx.accounts.owner.to_account_info(),
-                to: ctx.accounts.treasury.to_account_info(),
-            };
-            
-            anchor_lang::system_program::transfer(
-                CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    transfer_instruction,
-                ),
-                RENEWAL_FEE,
-            )?;
-            
-            msg!("Renewal fee paid: {} SOL", RENEWAL_FEE as f64 / 1_000_000_000.0);
+        // The authority and support operators are not charged
+        let is_operator = service.has_role(
+            ctx.accounts.owner.key(),
+            &ctx.accounts.owner_role,
+            RoleKind::Operator,
+        );
+        
+        if !is_operator {
+            // Regular users pay the TLD's renewal price for each year
+            let renewal_fee = ctx
+                .accounts
+                .tld
+                .renewal_price
+                .checked_mul(years as u64)
+                .ok_or(ErrorCode::MathOverflow)?;
+            match TokenPayment::load(
+                &ctx.accounts.token_price,
+                &ctx.accounts.payer_payment_account,
+                &ctx.accounts.treasury_payment_account,
+                &ctx.accounts.tld_owner_payment_account,
+                ctx.accounts.token_program.as_ref().map(|program| program.to_account_info()),
+                &ctx.accounts.tld,
+                ctx.accounts.owner.key(),
+                service.treasury,
+            )? {
+                Some(payment) => {
+                    let token_fee = payment
+                        .token_price
+                        .renewal_price
+                        .checked_mul(years as u64)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                    payment.pay_revenue(&ctx.accounts.owner.to_account_info(), token_fee)?;
+                    
+                    msg!("Renewal fee paid: {} tokens of {}", token_fee, payment.token_price.mint);
+                }
+                None => {
+                    // USD-priced TLDs are converted to lamports at the oracle price
+                    let renewal_fee = lamports_for_fee(
+                        renewal_fee,
+                        &ctx.accounts.tld,
+                        service,
+                        &ctx.accounts.price_feed,
+                        clock.unix_timestamp,
+                    )?;
+                    
+                    require!(
+                        ctx.accounts.owner.lamports() >= renewal_fee,
+                        ErrorCode::InsufficientFunds
+                    );
+                    
+                    // Split the renewal fee between the treasury and the TLD owner
+                    pay_revenue(
+                        &ctx.accounts.owner.to_account_info(),
+                        &ctx.accounts.treasury,
+                        &ctx.accounts.tld_owner,
+                        &ctx.accounts.system_program.to_account_info(),
+                        &ctx.accounts.tld,
+                        renewal_fee,
+                    )?;
+                    
+                    msg!("Renewal fee paid: {} SOL", renewal_fee as f64 / 1_000_000_000.0);
+                }
+            }
         } else {
             msg!("Authority wallet - FREE renewal");
         }
         
-        // Extend domain expiration by 1 year
-        domain.expires_at = clock.unix_timestamp + (365 * 24 * 60 * 60);
+        domain.expires_at = new_expires_at;
         
-        msg!("Domain renewed: {}.{}", domain.name, domain.tld);
+        msg!("Domain renewed: {}.{} for {} year(s)", domain.name, domain.tld, years);
+        msg!("Expires at: {}", new_expires_at);
         Ok(())
     }
 
     pub fn transfer_domain_nft(
         ctx: Context<TransferDomainNFT>,
         sale_price: u64,
+        max_price: u64,
     ) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_TRANSFERS)?;
+        
         let domain = &mut ctx.accounts.domain;
         
         require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
         require!(domain.owner == ctx.accounts.current_owner.key(), ErrorCode::Unauthorized);
         
+        // Sales need a price both parties agree to; use the gift instruction for free transfers
+        require!(sale_price > 0, ErrorCode::InvalidPrice);
+        require!(sale_price <= max_price, ErrorCode::PriceAboveMaximum);
+        
         // Calculate royalty (5%)
-        let royalty = sale_price * ROYALTY_PERCENTAGE as u64 / 10000;
+        let royalty = sale_royalty(sale_price)?;
         let seller_amount = sale_price - royalty;
         
         msg!("Transferring domain NFT: {}.{}", domain.name, domain.tld);
@@ -240,33 +775,63 @@ pub mod digitalpulse_tld {
         msg!("Royalty (5%): {} SOL", royalty as f64 / 1_000_000_000.0);
         msg!("Seller receives: {} SOL", seller_amount as f64 / 1_000_000_000.0);
         
-        // Transfer sale amount to seller
-        let transfer_seller = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.new_owner.to_account_info(),
-            to: ctx.accounts.current_owner.to_account_info(),
-        };
-        
-        anchor_lang::system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                transfer_seller,
-            ),
-            seller_amount,
-        )?;
-        
-        // Transfer royalty to treasury
-        let transfer_royalty = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.new_owner.to_account_info(),
-            to: ctx.accounts.treasury.to_account_info(),
-        };
-        
-        anchor_lang::system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                transfer_royalty,
-            ),
-            royalty,
-        )?;
+        match TokenPayment::load(
+            &ctx.accounts.token_price,
+            &ctx.accounts.payer_payment_account,
+            &ctx.accounts.treasury_payment_account,
+            &ctx.accounts.tld_owner_payment_account,
+            Some(ctx.accounts.token_program.to_account_info()),
+            &ctx.accounts.tld,
+            ctx.accounts.new_owner.key(),
+            ctx.accounts.service.treasury,
+        )? {
+            Some(payment) => {
+                let seller_payment_account = ctx
+                    .accounts
+                    .seller_payment_account
+                    .as_ref()
+                    .ok_or(ErrorCode::IncompleteTokenPayment)?;
+                require_keys_eq!(
+                    seller_payment_account.mint,
+                    payment.token_price.mint,
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    seller_payment_account.owner,
+                    ctx.accounts.current_owner.key(),
+                    ErrorCode::InvalidPaymentAccount
+                );
+                
+                let buyer = ctx.accounts.new_owner.to_account_info();
+                payment.pay(&buyer, &seller_payment_account.to_account_info(), seller_amount)?;
+                payment.pay_revenue(&buyer, royalty)?;
+            }
+            None => {
+                // Transfer sale amount to seller
+                let transfer_seller = anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.new_owner.to_account_info(),
+                    to: ctx.accounts.current_owner.to_account_info(),
+                };
+                
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        transfer_seller,
+                    ),
+                    seller_amount,
+                )?;
+                
+                // Split the royalty between the treasury and the TLD owner
+                pay_revenue(
+                    &ctx.accounts.new_owner.to_account_info(),
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.tld_owner,
+                    &ctx.accounts.system_program.to_account_info(),
+                    &ctx.accounts.tld,
+                    royalty,
+                )?;
+            }
+        }
         
         // Transfer NFT token
         let cpi_accounts = anchor_spl::token::Transfer {
@@ -281,158 +846,4085 @@ pub mod digitalpulse_tld {
         
         domain.owner = ctx.accounts.new_owner.key();
         
-        msg!("Domain NFT transferred to: {}", ctx.accounts.new_owner.key());
-        Ok(())
-    }
-
-    pub fn update_authority(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
-        let service = &mut ctx.accounts.service;
-        
-        // Verify current authority
-        require!(
-            ctx.accounts.authority.key() == service.authority,
-            ErrorCode::Unauthorized
-        );
-        
-        let old_authority = service.authority;
-        service.authority = new_authority;
+        // The seller's primary name must not keep pointing at a domain they no longer own
+        clear_primary_domain_if_matches(
+            &ctx.accounts.current_owner_primary.to_account_info(),
+            domain.key(),
+        )?;
         
-        msg!("Authority wallet updated");
-        msg!("Old authority: {}", old_authority);
-        msg!("New authority: {}", new_authority);
+        msg!("Domain NFT transferred to: {}", ctx.accounts.new_owner.key());
         
-        emit!(AuthorityUpdated {
-            old_authority,
-            new_authority,
+        emit!(DomainTransferred {
+            domain: domain.key(),
+            previous_owner: ctx.accounts.current_owner.key(),
+            new_owner: domain.owner,
+            sale_price,
         });
         
         Ok(())
     }
 
-    pub fn update_treasury(ctx: Context<UpdateTreasury>, new_treasury: Pubkey) -> Result<()> {
-        let service = &mut ctx.accounts.service;
+    pub fn gift_domain_nft(ctx: Context<GiftDomainNFT>) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_TRANSFERS)?;
         
-        // Verify current authority
+        let domain = &mut ctx.accounts.domain;
+        
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        require!(domain.owner == ctx.accounts.current_owner.key(), ErrorCode::Unauthorized);
+        
+        // Transfer NFT token
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.current_owner_token_account.to_account_info(),
+            to: ctx.accounts.new_owner_token_account.to_account_info(),
+            authority: ctx.accounts.current_owner.to_account_info(),
+        };
+        
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        anchor_spl::token::transfer(cpi_ctx, 1)?;
+        
+        domain.owner = ctx.accounts.new_owner.key();
+        
+        // The giver's primary name must not keep pointing at a domain they no longer own
+        clear_primary_domain_if_matches(
+            &ctx.accounts.current_owner_primary.to_account_info(),
+            domain.key(),
+        )?;
+        
+        msg!("Domain gifted: {}.{} to {}", domain.name, domain.tld, domain.owner);
+        
+        emit!(DomainTransferred {
+            domain: domain.key(),
+            previous_owner: ctx.accounts.current_owner.key(),
+            new_owner: domain.owner,
+            sale_price: 0,
+        });
+        
+        Ok(())
+    }
+
+    pub fn reclaim_expired_domain_nft(
+        ctx: Context<ReclaimExpiredDomainNFT>,
+        years: u8,
+    ) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_REGISTRATIONS)?;
+        
+        let domain = &mut ctx.accounts.domain;
+        let tld = &ctx.accounts.tld;
+        let service = &ctx.accounts.service;
+        let clock = Clock::get()?;
+        
+        // Validate TLD is active
+        require!(tld.is_active, ErrorCode::TLDNotActive);
+        
+        // Only names past expiry and the grace period can change hands
+        domain.check_reclaimable(clock.unix_timestamp, service.grace_period)?;
+        
+        // Names reserved since they were registered go back to the TLD's managers
+        require!(ctx.accounts.reserved_name.data_is_empty(), ErrorCode::NameIsReserved);
+        
+        let expires_at = extend_expiry(
+            clock.unix_timestamp,
+            clock.unix_timestamp,
+            years,
+            service.max_prepaid_years,
+        )?;
+        
+        // Premium and length-tiered prices take precedence over the TLD base price
+        let (price_per_year, _) = registration_price(
+            tld.price,
+            &domain.name,
+            &ctx.accounts.pricing_schedule.to_account_info(),
+            &ctx.accounts.premium_name.to_account_info(),
+        )?;
+        
+        // Released names are sold through a declining-price auction starting when the grace period ends
+        let release_premium = release_auction_premium(
+            tld,
+            price_per_year,
+            domain.expires_at.saturating_add(service.grace_period),
+            clock.unix_timestamp,
+        )?;
+        
+        // The authority and support operators are not charged
+        let is_operator = service.has_role(
+            ctx.accounts.new_owner.key(),
+            &ctx.accounts.new_owner_role,
+            RoleKind::Operator,
+        );
+        
+        if !is_operator {
+            // Regular users pay the name's registration price for each year, plus any release premium
+            let registration_fee = price_per_year
+                .checked_mul(years as u64)
+                .and_then(|fee| fee.checked_add(release_premium))
+                .ok_or(ErrorCode::MathOverflow)?;
+            match TokenPayment::load(
+                &ctx.accounts.token_price,
+                &ctx.accounts.payer_payment_account,
+                &ctx.accounts.treasury_payment_account,
+                &ctx.accounts.tld_owner_payment_account,
+                Some(ctx.accounts.token_program.to_account_info()),
+                tld,
+                ctx.accounts.new_owner.key(),
+                service.treasury,
+            )? {
+                Some(payment) => {
+                    // Token prices scale premium and length-tiered prices by the same ratio
+                    let token_fee = scale_price(registration_fee, tld.price, payment.token_price.price)?;
+                    payment.pay_revenue(&ctx.accounts.new_owner.to_account_info(), token_fee)?;
+                    
+                    msg!("Registration fee paid: {} tokens of {}", token_fee, payment.token_price.mint);
+                }
+                None => {
+                    // USD-priced TLDs are converted to lamports at the oracle price
+                    let registration_fee = lamports_for_fee(
+                        registration_fee,
+                        tld,
+                        service,
+                        &ctx.accounts.price_feed,
+                        clock.unix_timestamp,
+                    )?;
+                    
+                    require!(
+                        ctx.accounts.new_owner.lamports() >= registration_fee,
+                        ErrorCode::InsufficientFunds
+                    );
+                    
+                    // Split the registration fee between the treasury and the TLD owner
+                    pay_revenue(
+                        &ctx.accounts.new_owner.to_account_info(),
+                        &ctx.accounts.treasury,
+                        &ctx.accounts.tld_owner,
+                        &ctx.accounts.system_program.to_account_info(),
+                        tld,
+                        registration_fee,
+                    )?;
+                    
+                    msg!("Registration fee paid: {} SOL", registration_fee as f64 / 1_000_000_000.0);
+                }
+            }
+        } else {
+            msg!("Authority wallet - FREE registration");
+        }
+        
+        // Hand the name to the new registrant with a fresh registration period
+        let previous_owner = domain.owner;
+        domain.owner = ctx.accounts.new_owner.key();
+        domain.registered_at = clock.unix_timestamp;
+        domain.expires_at = expires_at;
+        domain.is_active = true;
+        
+        // Supersede the stale NFT held by the previous owner with a fresh one
+        domain.mint = ctx.accounts.mint.key();
+        
+        let full_domain = format!("{}.{}", domain.name, domain.tld);
+        mint_domain_nft(
+            DomainNftAccounts {
+                mint: ctx.accounts.mint.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                owner: ctx.accounts.new_owner.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                metadata_program: ctx.accounts.metadata_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+            &full_domain,
+            service.treasury,
+        )?;
+        
+        msg!("NFT Mint: {}", domain.mint);
+        
+        // The previous owner's primary name must not keep pointing at this domain
+        clear_primary_domain_if_matches(
+            &ctx.accounts.previous_owner_primary.to_account_info(),
+            domain.key(),
+        )?;
+        
+        msg!("Expired domain NFT reclaimed: {}.{}", domain.name, domain.tld);
+        msg!("Previous owner: {}", previous_owner);
+        msg!("New owner: {}", domain.owner);
+        
+        emit!(DomainReclaimed {
+            domain: domain.key(),
+            previous_owner,
+            new_owner: domain.owner,
+        });
+        
+        Ok(())
+    }
+
+    pub fn list_domain_nft(ctx: Context<ListDomainNFT>, price: u64) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        require!(price > 0, ErrorCode::InvalidPrice);
+        
+        let domain = &mut ctx.accounts.domain;
+        let listing = &mut ctx.accounts.listing;
+        
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
         require!(
-            ctx.accounts.authority.key() == service.authority,
-            ErrorCode::Unauthorized
+            domain.owner == ctx.accounts.seller.key(),
+            ErrorCode::NotDomainOwner
         );
         
-        let old_treasury = service.treasury;
-        service.treasury = new_treasury;
+        listing.domain = domain.key();
+        listing.seller = ctx.accounts.seller.key();
+        listing.mint = domain.mint;
+        listing.price = price;
+        listing.created_at = Clock::get()?.unix_timestamp;
         
-        msg!("Treasury wallet updated");
-        msg!("Old treasury: {}", old_treasury);
-        msg!("New treasury: {}", new_treasury);
+        // Escrow the domain NFT with the listing
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.seller_token_account.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+        };
         
-        emit!(TreasuryUpdated {
-            old_treasury,
-            new_treasury,
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        anchor_spl::token::transfer(cpi_ctx, 1)?;
+        
+        // The listing holds the domain until it is bought or cancelled
+        domain.owner = listing.key();
+        
+        msg!("Domain NFT listed: {}.{} for {} SOL", domain.name, domain.tld, price as f64 / 1_000_000_000.0);
+        
+        emit!(DomainListed {
+            domain: domain.key(),
+            seller: listing.seller,
+            price,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_listing(ctx: Context<UpdateListing>, price: u64) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        require!(price > 0, ErrorCode::InvalidPrice);
+        
+        let listing = &mut ctx.accounts.listing;
+        let old_price = listing.price;
+        listing.price = price;
+        
+        msg!("Listing price updated: {} -> {} lamports", old_price, price);
+        
+        emit!(ListingUpdated {
+            domain: listing.domain,
+            old_price,
+            new_price: price,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_listing_nft(ctx: Context<CancelListingNFT>) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let domain = &mut ctx.accounts.domain;
+        let authority = ctx.accounts.authority.key();
+        
+        if domain.owner == listing.key() {
+            // Only the seller can take back a domain the listing still holds
+            require!(authority == listing.seller, ErrorCode::Unauthorized);
+            domain.owner = listing.seller;
+        } else {
+            // The domain was reclaimed after expiring, so its new owner may clear the stale listing
+            require!(
+                authority == listing.seller || authority == domain.owner,
+                ErrorCode::Unauthorized
+            );
+        }
+        
+        // The escrowed NFT always goes back to the seller
+        release_escrowed_nft(
+            &listing.to_account_info(),
+            &[&[b"listing", listing.domain.as_ref(), &[*ctx.bumps.get("listing").unwrap()]]],
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.seller_token_account,
+            &ctx.accounts.seller,
+            &ctx.accounts.token_program,
+        )?;
+        
+        msg!("Listing cancelled: {}.{}", domain.name, domain.tld);
+        
+        emit!(ListingCancelled {
+            domain: domain.key(),
+            seller: listing.seller,
+        });
+        
+        Ok(())
+    }
+
+    pub fn buy_listing_nft(ctx: Context<BuyListingNFT>, max_price: u64) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        let listing = &ctx.accounts.listing;
+        let domain = &mut ctx.accounts.domain;
+        let sale_price = listing.price;
+        
+        // Protects the buyer from a price change landing before their purchase
+        require!(sale_price <= max_price, ErrorCode::PriceAboveMaximum);
+        require!(domain.owner == listing.key(), ErrorCode::ListingInactive);
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        let royalty = sale_royalty(sale_price)?;
+        let seller_amount = sale_price - royalty;
+        
+        msg!("Buying domain NFT: {}.{}", domain.name, domain.tld);
+        msg!("Sale price: {} SOL", sale_price as f64 / 1_000_000_000.0);
+        msg!("Royalty (5%): {} SOL", royalty as f64 / 1_000_000_000.0);
+        msg!("Seller receives: {} SOL", seller_amount as f64 / 1_000_000_000.0);
+        
+        // Transfer sale amount to seller
+        let transfer_seller = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.buyer.to_account_info(),
+            to: ctx.accounts.seller.to_account_info(),
+        };
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                transfer_seller,
+            ),
+            seller_amount,
+        )?;
+        
+        // Split the royalty between the treasury and the TLD owner
+        pay_revenue(
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.treasury,
+            &ctx.accounts.tld_owner,
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.tld,
+            royalty,
+        )?;
+        
+        // Hand the escrowed NFT to the buyer
+        release_escrowed_nft(
+            &listing.to_account_info(),
+            &[&[b"listing", listing.domain.as_ref(), &[*ctx.bumps.get("listing").unwrap()]]],
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.buyer_token_account,
+            &ctx.accounts.seller,
+            &ctx.accounts.token_program,
+        )?;
+        
+        domain.owner = ctx.accounts.buyer.key();
+        
+        // The seller's primary name must not keep pointing at a domain they no longer own
+        clear_primary_domain_if_matches(
+            &ctx.accounts.seller_primary.to_account_info(),
+            domain.key(),
+        )?;
+        
+        msg!("Domain NFT sold to: {}", domain.owner);
+        
+        emit!(DomainSold {
+            domain: domain.key(),
+            seller: listing.seller,
+            buyer: domain.owner,
+            price: sale_price,
+            royalty,
+        });
+        
+        Ok(())
+    }
+
+    pub fn make_offer(ctx: Context<MakeOffer>, amount: u64, expires_at: i64) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        let clock = Clock::get()?;
+        require!(amount > 0, ErrorCode::InvalidPrice);
+        require!(expires_at > clock.unix_timestamp, ErrorCode::InvalidOfferExpiry);
+        
+        let domain = &ctx.accounts.domain;
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(clock.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        let offer = &mut ctx.accounts.offer;
+        offer.domain = domain.key();
+        offer.bidder = ctx.accounts.bidder.key();
+        offer.amount = amount;
+        offer.created_at = clock.unix_timestamp;
+        offer.expires_at = expires_at;
+        offer.bump = *ctx.bumps.get("offer").unwrap();
+        
+        match (
+            &ctx.accounts.token_price,
+            &ctx.accounts.bidder_payment_account,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        ) {
+            (None, None, None, _) => {
+                // SOL bids are held in the offer account's own lamports
+                offer.mint = None;
+                
+                let transfer_instruction = anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.bidder.to_account_info(),
+                    to: offer.to_account_info(),
+                };
+                
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        transfer_instruction,
+                    ),
+                    amount,
+                )?;
+                
+                msg!("Offer made: {} SOL for {}.{}", amount as f64 / 1_000_000_000.0, domain.name, domain.tld);
+            }
+            (
+                Some(token_price),
+                Some(bidder_payment_account),
+                Some(escrow_token_account),
+                Some(token_program),
+            ) => {
+                // Token bids must use a mint accepted by the domain's TLD
+                require_keys_eq!(token_price.tld, ctx.accounts.tld.key(), ErrorCode::TokenNotAccepted);
+                require_keys_eq!(
+                    bidder_payment_account.mint,
+                    token_price.mint,
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    bidder_payment_account.owner,
+                    offer.bidder,
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    escrow_token_account.key(),
+                    get_associated_token_address(&offer.key(), &token_price.mint),
+                    ErrorCode::InvalidPaymentAccount
+                );
+                offer.mint = Some(token_price.mint);
+                
+                let cpi_accounts = anchor_spl::token::Transfer {
+                    from: bidder_payment_account.to_account_info(),
+                    to: escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                };
+                
+                anchor_spl::token::transfer(
+                    CpiContext::new(token_program.to_account_info(), cpi_accounts),
+                    amount,
+                )?;
+                
+                msg!("Offer made: {} tokens of {} for {}.{}", amount, token_price.mint, domain.name, domain.tld);
+            }
+            _ => return err!(ErrorCode::IncompleteTokenPayment),
+        }
+        
+        emit!(OfferMade {
+            offer: offer.key(),
+            domain: offer.domain,
+            bidder: offer.bidder,
+            mint: offer.mint,
+            amount,
+            expires_at,
+        });
+        
+        Ok(())
+    }
+
+    pub fn accept_offer_nft(ctx: Context<AcceptOfferNFT>) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        let offer = &ctx.accounts.offer;
+        let domain = &mut ctx.accounts.domain;
+        let clock = Clock::get()?;
+        
+        require!(clock.unix_timestamp < offer.expires_at, ErrorCode::OfferExpired);
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(clock.unix_timestamp, ctx.accounts.service.grace_period)?;
+        require!(
+            domain.owner == ctx.accounts.seller.key(),
+            ErrorCode::NotDomainOwner
+        );
+        
+        let royalty = sale_royalty(offer.amount)?;
+        let seller_amount = offer.amount - royalty;
+        
+        msg!("Accepting offer for: {}.{}", domain.name, domain.tld);
+        msg!("Offer amount: {}", offer.amount);
+        msg!("Royalty (5%): {}", royalty);
+        msg!("Seller receives: {}", seller_amount);
+        
+        match OfferEscrow::load(
+            offer,
+            &ctx.accounts.escrow_token_account,
+            Some(ctx.accounts.token_program.to_account_info()),
+        )? {
+            Some(escrow) => {
+                let seller_payment_account = ctx
+                    .accounts
+                    .seller_payment_account
+                    .as_ref()
+                    .ok_or(ErrorCode::IncompleteTokenPayment)?;
+                let treasury_payment_account = ctx
+                    .accounts
+                    .treasury_payment_account
+                    .as_ref()
+                    .ok_or(ErrorCode::IncompleteTokenPayment)?;
+                require_keys_eq!(
+                    seller_payment_account.mint,
+                    escrow.mint,
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    seller_payment_account.owner,
+                    ctx.accounts.seller.key(),
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    treasury_payment_account.key(),
+                    get_associated_token_address(&ctx.accounts.service.treasury, &escrow.mint),
+                    ErrorCode::InvalidPaymentAccount
+                );
+                
+                let (treasury_royalty, tld_owner_royalty) = split_revenue(royalty, &ctx.accounts.tld)?;
+                escrow.pay(&seller_payment_account.to_account_info(), seller_amount)?;
+                escrow.pay(&treasury_payment_account.to_account_info(), treasury_royalty)?;
+                
+                if tld_owner_royalty > 0 {
+                    let tld_owner_payment_account = ctx
+                        .accounts
+                        .tld_owner_payment_account
+                        .as_ref()
+                        .ok_or(ErrorCode::IncompleteTokenPayment)?;
+                    require_keys_eq!(
+                        tld_owner_payment_account.key(),
+                        get_associated_token_address(&ctx.accounts.tld.owner, &escrow.mint),
+                        ErrorCode::InvalidPaymentAccount
+                    );
+                    escrow.pay(&tld_owner_payment_account.to_account_info(), tld_owner_royalty)?;
+                }
+                escrow.close(&ctx.accounts.bidder)?;
+            }
+            None => {
+                // Pay out of the offer account; its rent returns to the bidder when it closes
+                let offer_info = offer.to_account_info();
+                **offer_info.try_borrow_mut_lamports()? -= offer.amount;
+                **ctx.accounts.seller.try_borrow_mut_lamports()? += seller_amount;
+                credit_revenue(
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.tld_owner,
+                    &ctx.accounts.tld,
+                    royalty,
+                )?;
+            }
+        }
+        
+        // Transfer NFT token
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.seller_token_account.to_account_info(),
+            to: ctx.accounts.bidder_token_account.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+        };
+        
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        anchor_spl::token::transfer(cpi_ctx, 1)?;
+        
+        domain.owner = offer.bidder;
+        
+        // The seller's primary name must not keep pointing at a domain they no longer own
+        clear_primary_domain_if_matches(
+            &ctx.accounts.seller_primary.to_account_info(),
+            domain.key(),
+        )?;
+        
+        msg!("Domain transferred to bidder: {}", offer.bidder);
+        
+        emit!(OfferAccepted {
+            offer: offer.key(),
+            domain: domain.key(),
+            seller: ctx.accounts.seller.key(),
+            bidder: offer.bidder,
+            amount: offer.amount,
+            royalty,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
+        refund_offer(
+            &ctx.accounts.offer,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.bidder_payment_account,
+            &ctx.accounts.bidder.to_account_info(),
+            ctx.accounts.token_program.as_ref().map(|program| program.to_account_info()),
+        )?;
+        
+        msg!("Offer cancelled by bidder: {}", ctx.accounts.bidder.key());
+        Ok(())
+    }
+
+    pub fn reclaim_expired_offer(ctx: Context<ReclaimExpiredOffer>) -> Result<()> {
+        // Anyone can return an expired bid to its bidder
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.offer.expires_at,
+            ErrorCode::OfferNotExpired
+        );
+        
+        refund_offer(
+            &ctx.accounts.offer,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.bidder_payment_account,
+            &ctx.accounts.bidder,
+            ctx.accounts.token_program.as_ref().map(|program| program.to_account_info()),
+        )?;
+        
+        msg!("Expired offer returned to bidder: {}", ctx.accounts.bidder.key());
+        Ok(())
+    }
+
+    pub fn create_auction_nft(
+        ctx: Context<CreateAuctionNFT>,
+        reserve_price: u64,
+        min_increment: u64,
+        end_time: i64,
+    ) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        require!(reserve_price > 0, ErrorCode::InvalidPrice);
+        require!(min_increment > 0, ErrorCode::InvalidPrice);
+        
+        let domain = &mut ctx.accounts.domain;
+        let auction = &mut ctx.accounts.auction;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(now, ctx.accounts.service.grace_period)?;
+        require!(
+            domain.owner == ctx.accounts.seller.key(),
+            ErrorCode::NotDomainOwner
+        );
+        
+        // Auctions must end while the domain is still registered
+        require!(
+            end_time > now
+                && end_time - now <= MAX_AUCTION_DURATION
+                && end_time < domain.expires_at,
+            ErrorCode::InvalidAuctionEndTime
+        );
+        
+        auction.domain = domain.key();
+        auction.seller = ctx.accounts.seller.key();
+        auction.mint = domain.mint;
+        auction.reserve_price = reserve_price;
+        auction.min_increment = min_increment;
+        auction.end_time = end_time;
+        auction.highest_bidder = None;
+        auction.highest_bid = 0;
+        auction.bump = *ctx.bumps.get("auction").unwrap();
+        
+        // Escrow the domain NFT with the auction
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.seller_token_account.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+        };
+        
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        anchor_spl::token::transfer(cpi_ctx, 1)?;
+        
+        // The auction holds the domain until it is settled or cancelled
+        domain.owner = auction.key();
+        
+        msg!("Auction created: {}.{}", domain.name, domain.tld);
+        msg!("Reserve price: {} SOL, ends at {}", reserve_price as f64 / 1_000_000_000.0, end_time);
+        
+        emit!(AuctionCreated {
+            auction: auction.key(),
+            domain: domain.key(),
+            seller: auction.seller,
+            reserve_price,
+            min_increment,
+            end_time,
         });
         
         Ok(())
     }
+
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        
+        let auction = &mut ctx.accounts.auction;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(now < auction.end_time, ErrorCode::AuctionEnded);
+        require!(
+            ctx.accounts.domain.owner == auction.key(),
+            ErrorCode::AuctionInactive
+        );
+        require!(
+            ctx.accounts.bidder.key() != auction.seller,
+            ErrorCode::Unauthorized
+        );
+        
+        // The first bid must meet the reserve; later bids must beat the high bid by the increment
+        let minimum_bid = match auction.highest_bidder {
+            Some(_) => auction
+                .highest_bid
+                .checked_add(auction.min_increment)
+                .ok_or(ErrorCode::MathOverflow)?,
+            None => auction.reserve_price,
+        };
+        require!(amount >= minimum_bid, ErrorCode::BidTooLow);
+        
+        // Escrow the new bid in the auction account
+        let transfer_instruction = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.bidder.to_account_info(),
+            to: auction.to_account_info(),
+        };
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                transfer_instruction,
+            ),
+            amount,
+        )?;
+        
+        // Refund the previous high bidder out of escrow
+        if let Some(previous_bidder) = auction.highest_bidder {
+            let previous_bidder_info = ctx
+                .accounts
+                .previous_bidder
+                .as_ref()
+                .ok_or(ErrorCode::InvalidBidder)?;
+            require_keys_eq!(
+                previous_bidder_info.key(),
+                previous_bidder,
+                ErrorCode::InvalidBidder
+            );
+            
+            **auction.to_account_info().try_borrow_mut_lamports()? -= auction.highest_bid;
+            **previous_bidder_info.try_borrow_mut_lamports()? += auction.highest_bid;
+            
+            msg!("Previous bid refunded: {} SOL", auction.highest_bid as f64 / 1_000_000_000.0);
+        }
+        
+        auction.highest_bidder = Some(ctx.accounts.bidder.key());
+        auction.highest_bid = amount;
+        
+        // Late bids push the end out so other bidders can respond
+        if auction.end_time - now < AUCTION_EXTENSION_WINDOW {
+            auction.end_time = now + AUCTION_EXTENSION_WINDOW;
+            msg!("Auction extended to {}", auction.end_time);
+        }
+        
+        msg!("Bid placed: {} SOL by {}", amount as f64 / 1_000_000_000.0, ctx.accounts.bidder.key());
+        
+        emit!(BidPlaced {
+            auction: auction.key(),
+            bidder: ctx.accounts.bidder.key(),
+            amount,
+            end_time: auction.end_time,
+        });
+        
+        Ok(())
+    }
+
+    pub fn settle_auction_nft(ctx: Context<SettleAuctionNFT>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let domain = &mut ctx.accounts.domain;
+        
+        require!(
+            Clock::get()?.unix_timestamp >= auction.end_time,
+            ErrorCode::AuctionNotEnded
+        );
+        
+        // The high bidder wins the domain; without bids it goes back to the seller
+        let recipient = auction.highest_bidder.unwrap_or(auction.seller);
+        require_keys_eq!(
+            ctx.accounts.recipient.key(),
+            recipient,
+            ErrorCode::InvalidBidder
+        );
+        
+        let highest_bid = auction.highest_bid;
+        **auction.to_account_info().try_borrow_mut_lamports()? -= highest_bid;
+        
+        if domain.owner != auction.key() {
+            // The domain expired and was reclaimed during the auction, so the bid is refunded
+            **ctx.accounts.recipient.try_borrow_mut_lamports()? += highest_bid;
+            
+            // Burn the stale NFT left in escrow, since the reclaimed domain has a new one
+            let signer_seeds: &[&[&[u8]]] = &[&[b"auction", auction.domain.as_ref(), &[auction.bump]]];
+            let cpi_accounts = anchor_spl::token::Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            anchor_spl::token::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds,
+                ),
+                1,
+            )?;
+            
+            let cpi_accounts = anchor_spl::token::CloseAccount {
+                account: ctx.accounts.escrow_token_account.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            anchor_spl::token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ))?;
+            
+            msg!("Auction void, domain was reclaimed: {}.{}", domain.name, domain.tld);
+            
+            emit!(AuctionSettled {
+                auction: auction.key(),
+                domain: domain.key(),
+                seller: auction.seller,
+                winner: None,
+                price: 0,
+                royalty: 0,
+            });
+            
+            return Ok(());
+        }
+        
+        // Only a sale to the high bidder is paused; refunds and unsold
+        // auctions settle regardless, so escrowed funds are never stuck
+        if auction.highest_bidder.is_some() {
+            ctx.accounts.service.check_not_paused(PAUSE_MARKETPLACE)?;
+        }
+        
+        let royalty = sale_royalty(highest_bid)?;
+        **ctx.accounts.seller.try_borrow_mut_lamports()? += highest_bid - royalty;
+        credit_revenue(
+            &ctx.accounts.treasury,
+            &ctx.accounts.tld_owner,
+            &ctx.accounts.tld,
+            royalty,
+        )?;
+        
+        // Hand the escrowed NFT to the winner, or back to the seller
+        release_escrowed_nft(
+            &auction.to_account_info(),
+            &[&[b"auction", auction.domain.as_ref(), &[auction.bump]]],
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.recipient_token_account,
+            &ctx.accounts.seller,
+            &ctx.accounts.token_program,
+        )?;
+        
+        domain.owner = recipient;
+        
+        if auction.highest_bidder.is_some() {
+            // The seller's primary name must not keep pointing at a domain they no longer own
+            clear_primary_domain_if_matches(
+                &ctx.accounts.seller_primary.to_account_info(),
+                domain.key(),
+            )?;
+            
+            msg!("Auction won by {} for {} SOL", recipient, highest_bid as f64 / 1_000_000_000.0);
+            msg!("Royalty (5%): {} SOL", royalty as f64 / 1_000_000_000.0);
+        } else {
+            msg!("Auction ended without bids, domain returned to seller");
+        }
+        
+        emit!(AuctionSettled {
+            auction: auction.key(),
+            domain: domain.key(),
+            seller: auction.seller,
+            winner: auction.highest_bidder,
+            price: highest_bid,
+            royalty,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_auction_nft(ctx: Context<CancelAuctionNFT>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let domain = &mut ctx.accounts.domain;
+        
+        // Once a bid is in, the auction has to run to settlement
+        require!(auction.highest_bidder.is_none(), ErrorCode::AuctionHasBids);
+        
+        if domain.owner == auction.key() {
+            domain.owner = auction.seller;
+        }
+        
+        // The escrowed NFT always goes back to the seller
+        release_escrowed_nft(
+            &auction.to_account_info(),
+            &[&[b"auction", auction.domain.as_ref(), &[auction.bump]]],
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.seller_token_account,
+            &ctx.accounts.seller.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        msg!("Auction cancelled: {}.{}", domain.name, domain.tld);
+        
+        emit!(AuctionCancelled {
+            auction: auction.key(),
+            domain: domain.key(),
+            seller: auction.seller,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_record(
+        ctx: Context<SetRecord>,
+        record_type: RecordType,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_RECORDS)?;
+        
+        require!(data.len() <= MAX_RECORD_DATA_LEN, ErrorCode::RecordDataTooLong);
+        record_type.validate(&data)?;
+        
+        let domain = &ctx.accounts.domain;
+        
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        // Verify domain ownership
+        require!(
+            domain.owner == ctx.accounts.owner.key(),
+            ErrorCode::NotDomainOwner
+        );
+        
+        // Grow or shrink the record account to fit the new data
+        resize_account(
+            &ctx.accounts.record.to_account_info(),
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Record::space(data.len()),
+        )?;
+        
+        let record = &mut ctx.accounts.record;
+        record.domain = domain.key();
+        record.record_type = record_type;
+        record.data = data;
+        record.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Record {:?} set for {}.{}", record_type, domain.name, domain.tld);
+        
+        emit!(RecordSet {
+            domain: domain.key(),
+            record_type,
+            data: record.data.clone(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn delete_record(ctx: Context<DeleteRecord>) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_RECORDS)?;
+        
+        let domain = &ctx.accounts.domain;
+        let record_type = ctx.accounts.record.record_type;
+        
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        // Verify domain ownership
+        require!(
+            domain.owner == ctx.accounts.owner.key(),
+            ErrorCode::NotDomainOwner
+        );
+        
+        msg!("Record {:?} deleted for {}.{}", record_type, domain.name, domain.tld);
+        
+        emit!(RecordDeleted {
+            domain: domain.key(),
+            record_type,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_primary_domain(ctx: Context<SetPrimaryDomain>) -> Result<()> {
+        let domain = &ctx.accounts.domain;
+        
+        require!(domain.is_active, ErrorCode::DomainNotActive);
+        domain.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        // Verify domain ownership
+        require!(
+            domain.owner == ctx.accounts.owner.key(),
+            ErrorCode::NotDomainOwner
+        );
+        
+        let primary = &mut ctx.accounts.primary;
+        primary.owner = ctx.accounts.owner.key();
+        primary.domain = domain.key();
+        
+        msg!("Primary domain for {} set to {}.{}", primary.owner, domain.name, domain.tld);
+        
+        emit!(PrimaryDomainSet {
+            owner: primary.owner,
+            domain: primary.domain,
+        });
+        
+        Ok(())
+    }
+
+    pub fn clear_primary_domain(ctx: Context<ClearPrimaryDomain>) -> Result<()> {
+        let primary = &ctx.accounts.primary;
+        
+        msg!("Primary domain cleared for {}", primary.owner);
+        
+        emit!(PrimaryDomainCleared {
+            owner: primary.owner,
+            domain: primary.domain,
+        });
+        
+        Ok(())
+    }
+
+    pub fn configure_subdomains(
+        ctx: Context<ConfigureSubdomains>,
+        fee: u64,
+        is_open: bool,
+    ) -> Result<()> {
+        let parent = &ctx.accounts.parent;
+        
+        require!(parent.is_active, ErrorCode::DomainNotActive);
+        parent.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        // Verify domain ownership
+        require!(
+            parent.owner == ctx.accounts.owner.key(),
+            ErrorCode::NotDomainOwner
+        );
+        
+        let config = &mut ctx.accounts.config;
+        config.parent = parent.key();
+        config.fee = fee;
+        config.is_open = is_open;
+        
+        msg!("Subdomain issuance for {}.{} updated", parent.name, parent.tld);
+        msg!("Open: {}, fee: {} lamports", is_open, fee);
+        
+        emit!(SubdomainConfigUpdated {
+            parent: parent.key(),
+            fee,
+            is_open,
+        });
+        
+        Ok(())
+    }
+
+    pub fn create_subdomain(
+        ctx: Context<CreateSubdomain>,
+        name: String,
+        owner: Pubkey,
+        is_revocable: bool,
+    ) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_REGISTRATIONS)?;
+        
+        let name = validation::normalize(&name);
+        validation::validate_domain_name(&name, false)?;
+        
+        let parent = &ctx.accounts.parent;
+        let creator = &ctx.accounts.creator;
+        
+        require!(parent.is_active, ErrorCode::DomainNotActive);
+        parent.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        require!(
+            ctx.accounts.parent_owner.key() == parent.owner,
+            ErrorCode::Unauthorized
+        );
+        
+        // The parent owner issues subdomains freely; anyone else needs open issuance
+        let is_parent_owner = creator.key() == parent.owner;
+        let is_revocable = if is_parent_owner {
+            is_revocable
+        } else {
+            let config = ctx
+                .accounts
+                .config
+                .as_ref()
+                .ok_or(ErrorCode::SubdomainsNotOpen)?;
+            require!(config.is_open, ErrorCode::SubdomainsNotOpen);
+            require!(owner == creator.key(), ErrorCode::Unauthorized);
+            
+            if config.fee > 0 {
+                require!(
+                    creator.lamports() >= config.fee,
+                    ErrorCode::InsufficientFunds
+                );
+                
+                // Transfer subdomain fee to the parent owner
+                let transfer_instruction = anchor_lang::system_program::Transfer {
+                    from: creator.to_account_info(),
+                    to: ctx.accounts.parent_owner.to_account_info(),
+                };
+                
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        transfer_instruction,
+                    ),
+                    config.fee,
+                )?;
+                
+                msg!("Subdomain fee paid: {} SOL", config.fee as f64 / 1_000_000_000.0);
+            }
+            
+            // Self-issued subdomains cannot be taken back by the parent owner
+            false
+        };
+        
+        let subdomain = &mut ctx.accounts.subdomain;
+        subdomain.parent = parent.key();
+        subdomain.name = name.clone();
+        subdomain.owner = owner;
+        subdomain.created_at = Clock::get()?.unix_timestamp;
+        subdomain.is_revocable = is_revocable;
+        
+        msg!("Subdomain created: {}.{}.{}", name, parent.name, parent.tld);
+        
+        emit!(SubdomainCreated {
+            parent: parent.key(),
+            subdomain: subdomain.key(),
+            owner,
+            is_revocable,
+        });
+        
+        Ok(())
+    }
+
+    pub fn revoke_subdomain(ctx: Context<RevokeSubdomain>) -> Result<()> {
+        let parent = &ctx.accounts.parent;
+        let subdomain = &ctx.accounts.subdomain;
+        
+        parent.check_not_expired(Clock::get()?.unix_timestamp, ctx.accounts.service.grace_period)?;
+        
+        // Verify domain ownership
+        require!(
+            parent.owner == ctx.accounts.parent_owner.key(),
+            ErrorCode::NotDomainOwner
+        );
+        // Subdomains issued under a previous registration of the parent can always be revoked
+        require!(
+            subdomain.is_revocable || subdomain.created_at < parent.registered_at,
+            ErrorCode::SubdomainNotRevocable
+        );
+        
+        msg!("Subdomain revoked: {}.{}.{}", subdomain.name, parent.name, parent.tld);
+        
+        emit!(SubdomainRemoved {
+            parent: parent.key(),
+            subdomain: subdomain.key(),
+            owner: subdomain.owner,
+        });
+        
+        Ok(())
+    }
+
+    pub fn release_subdomain(ctx: Context<ReleaseSubdomain>) -> Result<()> {
+        let subdomain = &ctx.accounts.subdomain;
+        
+        require!(
+            subdomain.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        
+        msg!("Subdomain released: {}", subdomain.name);
+        
+        emit!(SubdomainRemoved {
+            parent: subdomain.parent,
+            subdomain: subdomain.key(),
+            owner: subdomain.owner,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_pricing_schedule(
+        ctx: Context<SetPricingSchedule>,
+        tiers: Vec<PriceTier>,
+    ) -> Result<()> {
+        require!(tiers.len() <= MAX_PRICE_TIERS, ErrorCode::TooManyPriceTiers);
+        
+        // Tiers must be ordered by strictly increasing length band
+        for (index, tier) in tiers.iter().enumerate() {
+            require!(tier.price > 0, ErrorCode::InvalidPrice);
+            if index > 0 {
+                require!(
+                    tier.max_length > tiers[index - 1].max_length,
+                    ErrorCode::InvalidPriceTiers
+                );
+            }
+        }
+        
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
+        
+        let schedule = &mut ctx.accounts.pricing_schedule;
+        schedule.tld = ctx.accounts.tld.key();
+        schedule.tiers = tiers;
+        
+        msg!("Pricing schedule updated for .{}", ctx.accounts.tld.name);
+        for tier in schedule.tiers.iter() {
+            msg!("Up to {} characters: {} lamports", tier.max_length, tier.price);
+        }
+        
+        emit!(PricingScheduleUpdated {
+            tld: schedule.tld,
+            tiers: schedule.tiers.clone(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_premium_name(
+        ctx: Context<SetPremiumName>,
+        name: String,
+        price: u64,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        
+        let name = validation::normalize(&name);
+        validation::validate_domain_name(&name, ctx.accounts.tld.allow_idna)?;
+        
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
+        
+        let premium_name = &mut ctx.accounts.premium_name;
+        premium_name.tld = ctx.accounts.tld.key();
+        premium_name.name = name;
+        premium_name.price = price;
+        
+        // The name's release auction starts when it is first made available
+        if premium_name.released_at == 0 {
+            premium_name.released_at = Clock::get()?.unix_timestamp;
+        }
+        
+        msg!(
+            "Premium price set: {}.{} at {} lamports",
+            premium_name.name,
+            ctx.accounts.tld.name,
+            price
+        );
+        
+        emit!(PremiumNameUpdated {
+            tld: premium_name.tld,
+            name: premium_name.name.clone(),
+            price,
+        });
+        
+        Ok(())
+    }
+
+    pub fn remove_premium_name(ctx: Context<RemovePremiumName>) -> Result<()> {
+        // TLD owners manage their own TLD's pricing
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::PricingManager,
+        )?;
+        
+        let premium_name = &ctx.accounts.premium_name;
+        
+        msg!("Premium price removed: {}.{}", premium_name.name, ctx.accounts.tld.name);
+        
+        emit!(PremiumNameUpdated {
+            tld: premium_name.tld,
+            name: premium_name.name.clone(),
+            price: 0,
+        });
+        
+        Ok(())
+    }
+
+    pub fn reserve_name(ctx: Context<ReserveName>, name: String) -> Result<()> {
+        let name = validation::normalize(&name);
+        validation::validate_domain_name(&name, ctx.accounts.tld.allow_idna)?;
+        
+        // TLD owners manage their own TLD's reserved names
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::Operator,
+        )?;
+        
+        let reserved_name = &mut ctx.accounts.reserved_name;
+        reserved_name.tld = ctx.accounts.tld.key();
+        reserved_name.name = name;
+        reserved_name.reserved_by = ctx.accounts.authority.key();
+        reserved_name.created_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Name reserved: {}.{}", reserved_name.name, ctx.accounts.tld.name);
+        
+        emit!(NameReserved {
+            tld: reserved_name.tld,
+            name: reserved_name.name.clone(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn unreserve_name(ctx: Context<UnreserveName>) -> Result<()> {
+        // TLD owners manage their own TLD's reserved names
+        ctx.accounts.tld.check_manager(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.service,
+            &ctx.accounts.authority_role,
+            RoleKind::Operator,
+        )?;
+        
+        let reserved_name = &ctx.accounts.reserved_name;
+        
+        msg!("Name unreserved: {}.{}", reserved_name.name, ctx.accounts.tld.name);
+        
+        emit!(NameUnreserved {
+            tld: reserved_name.tld,
+            name: reserved_name.name.clone(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn assign_reserved_name_nft(
+        ctx: Context<AssignReservedNameNFT>,
+        name: String,
+        years: u8,
+    ) -> Result<()> {
+        ctx.accounts.service.check_not_paused(PAUSE_REGISTRATIONS)?;
+        
+        let name = validation::normalize(&name);
+        let tld = &mut ctx.accounts.tld;
+        let service = &mut ctx.accounts.service;
+        let clock = Clock::get()?;
+        
+        // TLD owners manage their own TLD's reserved names
+        tld.check_manager(
+            ctx.accounts.authority.key(),
+            service,
+            &ctx.accounts.authority_role,
+            RoleKind::Operator,
+        )?;
+        require!(tld.is_active, ErrorCode::TLDNotActive);
+        
+        // A name reserved while registered can be reassigned once it is past
+        // expiry and the grace period, like any expired name
+        let domain = &mut ctx.accounts.domain;
+        let previous_owner = if domain.name.is_empty() {
+            None
+        } else {
+            domain.check_reclaimable(clock.unix_timestamp, service.grace_period)?;
+            Some(domain.owner)
+        };
+        
+        let expires_at = extend_expiry(
+            clock.unix_timestamp,
+            clock.unix_timestamp,
+            years,
+            service.max_prepaid_years,
+        )?;
+        
+        // Reserved names are assigned without a registration fee
+        domain.name = name;
+        domain.tld = tld.name.clone();
+        domain.owner = ctx.accounts.recipient.key();
+        domain.registered_at = clock.unix_timestamp;
+        domain.expires_at = expires_at;
+        domain.is_active = true;
+        domain.mint = ctx.accounts.mint.key();
+        
+        // Mint NFT (1 token) to the recipient with its metadata
+        let full_domain = format!("{}.{}", domain.name, domain.tld);
+        mint_domain_nft(
+            DomainNftAccounts {
+                mint: ctx.accounts.mint.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                owner: ctx.accounts.authority.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                metadata_program: ctx.accounts.metadata_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+            &full_domain,
+            service.treasury,
+        )?;
+        
+        match previous_owner {
+            Some(previous_owner) => {
+                // The previous owner's primary name must not keep pointing at this domain
+                clear_primary_domain_if_matches(
+                    &ctx.accounts.previous_owner_primary.to_account_info(),
+                    domain.key(),
+                )?;
+                
+                msg!("Previous owner: {}", previous_owner);
+            }
+            None => {
+                tld.total_domains += 1;
+                service.total_domains += 1;
+            }
+        }
+        
+        msg!("Reserved name assigned: {}.{} to {}", domain.name, domain.tld, domain.owner);
+        
+        emit!(ReservedNameAssigned {
+            tld: tld.key(),
+            domain: domain.key(),
+            name: domain.name.clone(),
+            owner: domain.owner,
+        });
+        
+        Ok(())
+    }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        
+        // Verify current authority
+        require!(
+            ctx.accounts.authority.key() == service.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        // Takes effect only once the new authority accepts, so a mistyped key can be corrected
+        service.pending_authority = Some(new_authority);
+        
+        msg!("Authority change proposed");
+        msg!("Current authority: {}", service.authority);
+        msg!("Pending authority: {}", new_authority);
+        
+        emit!(AuthorityProposed {
+            current_authority: service.authority,
+            pending_authority: new_authority,
+        });
+        
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        let pending_authority = service.pending_authority.ok_or(ErrorCode::NoPendingAuthority)?;
+        
+        // Only the proposed authority can complete the handover
+        require!(
+            ctx.accounts.new_authority.key() == pending_authority,
+            ErrorCode::Unauthorized
+        );
+        
+        let old_authority = service.authority;
+        service.authority = pending_authority;
+        service.pending_authority = None;
+        
+        msg!("Authority wallet updated");
+        msg!("Old authority: {}", old_authority);
+        msg!("New authority: {}", pending_authority);
+        
+        emit!(AuthorityUpdated {
+            old_authority,
+            new_authority: pending_authority,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_authority_proposal(ctx: Context<CancelAuthorityProposal>) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        
+        // Verify current authority
+        require!(
+            ctx.accounts.authority.key() == service.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        let pending_authority = service.pending_authority.take().ok_or(ErrorCode::NoPendingAuthority)?;
+        
+        msg!("Authority change cancelled: {}", pending_authority);
+        
+        emit!(AuthorityProposalCancelled {
+            current_authority: service.authority,
+            pending_authority,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_grace_period(ctx: Context<UpdateGracePeriod>, grace_period: i64) -> Result<()> {
+        require!(grace_period >= 0, ErrorCode::InvalidGracePeriod);
+        
+        let service = &mut ctx.accounts.service;
+        
+        // Verify authority or admin role
+        service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::Admin,
+        )?;
+        
+        let old_grace_period = service.grace_period;
+        service.grace_period = grace_period;
+        
+        msg!("Grace period updated: {} -> {} seconds", old_grace_period, grace_period);
+        
+        emit!(GracePeriodUpdated {
+            old_grace_period,
+            new_grace_period: grace_period,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_max_prepaid_years(
+        ctx: Context<UpdateMaxPrepaidYears>,
+        max_prepaid_years: u8,
+    ) -> Result<()> {
+        require!(max_prepaid_years > 0, ErrorCode::InvalidYears);
+        
+        let service = &mut ctx.accounts.service;
+        
+        // Verify authority or admin role
+        service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::Admin,
+        )?;
+        
+        let old_max_prepaid_years = service.max_prepaid_years;
+        service.max_prepaid_years = max_prepaid_years;
+        
+        msg!("Max prepaid years updated: {} -> {}", old_max_prepaid_years, max_prepaid_years);
+        
+        emit!(MaxPrepaidYearsUpdated {
+            old_max_prepaid_years,
+            new_max_prepaid_years: max_prepaid_years,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_price_feed(
+        ctx: Context<UpdatePriceFeed>,
+        price_feed: Pubkey,
+        max_price_age: i64,
+        max_price_confidence_bps: u16,
+    ) -> Result<()> {
+        require!(max_price_age > 0, ErrorCode::InvalidOracleConfig);
+        require!(max_price_confidence_bps <= 10_000, ErrorCode::InvalidOracleConfig);
+        
+        let service = &mut ctx.accounts.service;
+        
+        // Verify authority or admin role
+        service.check_role(
+            ctx.accounts.authority.key(),
+            &ctx.accounts.authority_role,
+            RoleKind::Admin,
+        )?;
+        
+        service.price_feed = price_feed;
+        service.max_price_age = max_price_age;
+        service.max_price_confidence_bps = max_price_confidence_bps;
+        
+        msg!("Price feed updated: {}", price_feed);
+        msg!("Max price age: {} seconds, max confidence: {} bps", max_price_age, max_price_confidence_bps);
+        
+        emit!(PriceFeedUpdated {
+            price_feed,
+            max_price_age,
+            max_price_confidence_bps,
+        });
+        
+        Ok(())
+    }
+
+    pub fn propose_treasury(ctx: Context<ProposeTreasury>, new_treasury: Pubkey) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        
+        // Verify current authority
+        require!(
+            ctx.accounts.authority.key() == service.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        // Takes effect only once the new treasury accepts, so a mistyped key can be corrected
+        service.pending_treasury = Some(new_treasury);
+        
+        msg!("Treasury change proposed");
+        msg!("Current treasury: {}", service.treasury);
+        msg!("Pending treasury: {}", new_treasury);
+        
+        emit!(TreasuryProposed {
+            current_treasury: service.treasury,
+            pending_treasury: new_treasury,
+        });
+        
+        Ok(())
+    }
+
+    pub fn accept_treasury(ctx: Context<AcceptTreasury>) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        let pending_treasury = service.pending_treasury.ok_or(ErrorCode::NoPendingTreasury)?;
+        
+        // The new treasury signs to prove the wallet is controlled
+        require!(
+            ctx.accounts.new_treasury.key() == pending_treasury,
+            ErrorCode::Unauthorized
+        );
+        
+        let old_treasury = service.treasury;
+        service.treasury = pending_treasury;
+        service.pending_treasury = None;
+        
+        msg!("Treasury wallet updated");
+        msg!("Old treasury: {}", old_treasury);
+        msg!("New treasury: {}", pending_treasury);
+        
+        emit!(TreasuryUpdated {
+            old_treasury,
+            new_treasury: pending_treasury,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_treasury_proposal(ctx: Context<CancelTreasuryProposal>) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        
+        // Verify current authority
+        require!(
+            ctx.accounts.authority.key() == service.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        let pending_treasury = service.pending_treasury.take().ok_or(ErrorCode::NoPendingTreasury)?;
+        
+        msg!("Treasury change cancelled: {}", pending_treasury);
+        
+        emit!(TreasuryProposalCancelled {
+            current_treasury: service.treasury,
+            pending_treasury,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_guardian(ctx: Context<UpdateGuardian>, guardian: Pubkey) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        
+        // Verify current authority
+        require!(
+            ctx.accounts.authority.key() == service.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        // The default pubkey removes the guardian
+        let old_guardian = service.guardian;
+        service.guardian = guardian;
+        
+        msg!("Guardian updated: {} -> {}", old_guardian, guardian);
+        
+        emit!(GuardianUpdated {
+            old_guardian,
+            new_guardian: guardian,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: u8) -> Result<()> {
+        require!(paused & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+        
+        let service = &mut ctx.accounts.service;
+        service.check_pauser(ctx.accounts.authority.key(), &ctx.accounts.authority_role)?;
+        
+        let previous_paused = service.paused;
+        service.paused = paused;
+        
+        msg!("Pause flags updated: {:#07b} -> {:#07b}", previous_paused, paused);
+        
+        emit!(ServicePauseUpdated {
+            previous_paused,
+            paused,
+            updated_by: ctx.accounts.authority.key(),
+        });
+        
+        Ok(())
+    }
+
+    pub fn grant_role(ctx: Context<GrantRole>, holder: Pubkey, kind: RoleKind) -> Result<()> {
+        let service = &ctx.accounts.service;
+        let authority = ctx.accounts.authority.key();
+        
+        // Only the authority appoints admins; admins appoint everyone else
+        if kind == RoleKind::Admin {
+            require!(authority == service.authority, ErrorCode::Unauthorized);
+        } else {
+            service.check_role(authority, &ctx.accounts.authority_role, RoleKind::Admin)?;
+        }
+        
+        let role = &mut ctx.accounts.role;
+        role.holder = holder;
+        role.kind = kind;
+        role.granted_by = authority;
+        role.granted_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Role {:?} granted to {}", kind, holder);
+        
+        emit!(RoleGranted {
+            holder,
+            kind,
+            granted_by: authority,
+        });
+        
+        Ok(())
+    }
+
+    pub fn revoke_role(ctx: Context<RevokeRole>) -> Result<()> {
+        let service = &ctx.accounts.service;
+        let role = &ctx.accounts.role;
+        let authority = ctx.accounts.authority.key();
+        
+        if role.kind == RoleKind::Admin {
+            require!(authority == service.authority, ErrorCode::Unauthorized);
+        } else {
+            service.check_role(authority, &ctx.accounts.authority_role, RoleKind::Admin)?;
+        }
+        
+        msg!("Role {:?} revoked from {}", role.kind, role.holder);
+        
+        emit!(RoleRevoked {
+            holder: role.holder,
+            kind: role.kind,
+            revoked_by: authority,
+        });
+        
+        Ok(())
+    }
+
+    pub fn migrate_service(ctx: Context<MigrateService>) -> Result<()> {
+        let info = ctx.accounts.service.to_account_info();
+        let legacy = load_legacy::<Service, LegacyService>(&info)?;
+        
+        // Only the authority recorded in the original account can migrate it
+        require!(
+            ctx.accounts.authority.key() == legacy.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        resize_account(
+            &info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Service::SPACE,
+        )?;
+        
+        // Settings added since the original deployment start at their defaults
+        let service = Service {
+            authority: legacy.authority,
+            treasury: legacy.treasury,
+            total_domains: legacy.total_domains,
+            total_tlds: legacy.total_tlds,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            max_prepaid_years: DEFAULT_MAX_PREPAID_YEARS,
+            price_feed: Pubkey::default(),
+            max_price_age: DEFAULT_MAX_PRICE_AGE,
+            max_price_confidence_bps: DEFAULT_MAX_PRICE_CONFIDENCE_BPS,
+            pending_authority: None,
+            pending_treasury: None,
+            guardian: Pubkey::default(),
+            paused: 0,
+        };
+        service.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Service migrated to the current layout");
+        
+        Ok(())
+    }
+
+    pub fn migrate_tld(ctx: Context<MigrateTLD>) -> Result<()> {
+        let info = ctx.accounts.tld.to_account_info();
+        let legacy = load_legacy::<TLD, LegacyTLD>(&info)?;
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.service.authority,
+            ErrorCode::Unauthorized
+        );
+        let (expected, _) = Pubkey::find_program_address(&[b"tld", legacy.name.as_bytes()], &crate::ID);
+        require_keys_eq!(info.key(), expected, anchor_lang::error::ErrorCode::ConstraintSeeds);
+        
+        resize_account(
+            &info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            TLD::space(legacy.name.len()),
+        )?;
+        
+        // Renewals keep costing the registration price until the TLD is repriced,
+        // and fees keep going to the treasury until a revenue share is set
+        let tld = TLD {
+            name: legacy.name,
+            price: legacy.price,
+            owner: legacy.owner,
+            created_at: legacy.created_at,
+            is_active: legacy.is_active,
+            total_domains: legacy.total_domains,
+            renewal_price: legacy.price,
+            allow_idna: false,
+            usd_pricing: false,
+            release_start_price: 0,
+            release_period: 0,
+            owner_share_bps: 0,
+        };
+        tld.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        
+        msg!("TLD migrated to the current layout: .{}", tld.name);
+        
+        Ok(())
+    }
+}
+
+// Helpers
+
+/// Reallocates a program-owned account to `new_len` bytes, topping up rent
+/// from `payer` when growing and refunding the excess to `payer` when shrinking.
+fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if account.data_len() == new_len {
+        return Ok(());
+    }
+    
+    let rent_minimum = Rent::get()?.minimum_balance(new_len);
+    let current_lamports = account.lamports();
+    
+    if rent_minimum > current_lamports {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent_minimum - current_lamports,
+        )?;
+    } else if current_lamports > rent_minimum {
+        let refund = current_lamports - rent_minimum;
+        **account.try_borrow_mut_lamports()? -= refund;
+        **payer.try_borrow_mut_lamports()? += refund;
+    }
+    
+    account.realloc(new_len, false)?;
+    Ok(())
+}
+
+/// Whether no wallet can mint more of a domain NFT or freeze it. Escrow flows
+/// only accept such mints. Mints created before `mint_domain_nft` revoked its
+/// authorities qualify once the original minter disables both.
+fn is_locked_domain_mint(mint: &Mint) -> bool {
+    mint.mint_authority.is_none() && mint.freeze_authority.is_none()
+}
+
+/// Accounts needed to mint a domain NFT and create its Metaplex metadata.
+struct DomainNftAccounts<'info> {
+    mint: AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+    metadata: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    metadata_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    rent: AccountInfo<'info>,
+}
+
+/// Mints the single domain NFT to the owner's token account and creates its
+/// metadata, with the treasury as creator and the standard royalty. The mint
+/// authority is then revoked; domain mints are created without a freeze
+/// authority, so no wallet can mint more or lock a token held in escrow.
+fn mint_domain_nft<'info>(
+    accounts: DomainNftAccounts<'info>,
+    full_domain: &str,
+    treasury: Pubkey,
+) -> Result<()> {
+    // Mint NFT (1 token)
+    let cpi_accounts = MintTo {
+        mint: accounts.mint.clone(),
+        to: accounts.token_account,
+        authority: accounts.owner.clone(),
+    };
+    let cpi_program = accounts.token_program.clone();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    mint_to(cpi_ctx, 1)?;
+    
+    // Create NFT metadata
+    let metadata_uri = format!("https://digitalpulse.pv/metadata/{}.json", full_domain);
+    
+    let creators = vec![
+        Creator {
+            address: treasury,
+            verified: false,
+            share: 100,
+        },
+    ];
+    
+    let data_v2 = DataV2 {
+        name: full_domain.to_string(),
+        symbol: String::from("DPULSE"),
+        uri: metadata_uri,
+        seller_fee_basis_points: ROYALTY_PERCENTAGE,
+        creators: Some(creators),
+        collection: None,
+        uses: None,
+    };
+    
+    let cpi_accounts = CreateMetadataAccountsV3 {
+        metadata: accounts.metadata,
+        mint: accounts.mint.clone(),
+        mint_authority: accounts.owner.clone(),
+        update_authority: accounts.owner.clone(),
+        payer: accounts.owner.clone(),
+        system_program: accounts.system_program,
+        rent: accounts.rent,
+    };
+    
+    let cpi_program = accounts.metadata_program;
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    
+    create_metadata_accounts_v3(
+        cpi_ctx,
+        data_v2,
+        true,  // is_mutable
+        true,  // update_authority_is_signer
+        None,  // collection_details
+    )?;
+    
+    let cpi_accounts = SetAuthority {
+        current_authority: accounts.owner,
+        account_or_mint: accounts.mint,
+    };
+    set_authority(
+        CpiContext::new(accounts.token_program, cpi_accounts),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+    
+    Ok(())
+}
+
+/// Whitelisted SPL token payment accounts, supplied in place of SOL.
+struct TokenPayment<'a, 'info> {
+    tld: &'a TLD,
+    token_price: &'a Account<'info, TokenPrice>,
+    payer_payment_account: &'a Account<'info, TokenAccount>,
+    treasury_payment_account: &'a Account<'info, TokenAccount>,
+    tld_owner_payment_account: Option<&'a Account<'info, TokenAccount>>,
+    token_program: AccountInfo<'info>,
+}
+
+impl<'a, 'info> TokenPayment<'a, 'info> {
+    /// Returns `None` when no token payment accounts are supplied (pay in SOL).
+    /// Fails if they are only partly supplied or don't match the TLD, payer and
+    /// the treasury's and TLD owner's associated token accounts for the mint.
+    #[allow(clippy::too_many_arguments)]
+    fn load(
+        token_price: &'a Option<Account<'info, TokenPrice>>,
+        payer_payment_account: &'a Option<Account<'info, TokenAccount>>,
+        treasury_payment_account: &'a Option<Account<'info, TokenAccount>>,
+        tld_owner_payment_account: &'a Option<Account<'info, TokenAccount>>,
+        token_program: Option<AccountInfo<'info>>,
+        tld: &'a Account<'info, TLD>,
+        payer: Pubkey,
+        treasury: Pubkey,
+    ) -> Result<Option<Self>> {
+        match (token_price, payer_payment_account, treasury_payment_account, token_program) {
+            (None, None, None, _) => Ok(None),
+            (
+                Some(token_price),
+                Some(payer_payment_account),
+                Some(treasury_payment_account),
+                Some(token_program),
+            ) => {
+                require_keys_eq!(token_price.tld, tld.key(), ErrorCode::TokenNotAccepted);
+                require_keys_eq!(
+                    payer_payment_account.mint,
+                    token_price.mint,
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    payer_payment_account.owner,
+                    payer,
+                    ErrorCode::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    treasury_payment_account.key(),
+                    get_associated_token_address(&treasury, &token_price.mint),
+                    ErrorCode::InvalidPaymentAccount
+                );
+                if let Some(tld_owner_payment_account) = tld_owner_payment_account {
+                    require_keys_eq!(
+                        tld_owner_payment_account.key(),
+                        get_associated_token_address(&tld.owner, &token_price.mint),
+                        ErrorCode::InvalidPaymentAccount
+                    );
+                }
+                
+                Ok(Some(Self {
+                    tld,
+                    token_price,
+                    payer_payment_account,
+                    treasury_payment_account,
+                    tld_owner_payment_account: tld_owner_payment_account.as_ref(),
+                    token_program,
+                }))
+            }
+            _ => err!(ErrorCode::IncompleteTokenPayment),
+        }
+    }
+    
+    fn pay(&self, authority: &AccountInfo<'info>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: self.payer_payment_account.to_account_info(),
+            to: to.clone(),
+            authority: authority.clone(),
+        };
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(self.token_program.clone(), cpi_accounts),
+            amount,
+        )
+    }
+    
+    /// Pays a fee or royalty, split between the treasury and the TLD owner.
+    fn pay_revenue(&self, authority: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let (treasury_amount, tld_owner_amount) = split_revenue(amount, self.tld)?;
+        self.pay(authority, &self.treasury_payment_account.to_account_info(), treasury_amount)?;
+        
+        if tld_owner_amount > 0 {
+            let tld_owner_payment_account = self
+                .tld_owner_payment_account
+                .ok_or(ErrorCode::IncompleteTokenPayment)?;
+            self.pay(authority, &tld_owner_payment_account.to_account_info(), tld_owner_amount)?;
+        }
+        Ok(())
+    }
+}
+
+/// Escrow token account of an SPL token offer, owned by the offer PDA.
+struct OfferEscrow<'a, 'info> {
+    offer: &'a Account<'info, Offer>,
+    escrow_token_account: &'a Account<'info, TokenAccount>,
+    token_program: AccountInfo<'info>,
+    mint: Pubkey,
+}
+
+impl<'a, 'info> OfferEscrow<'a, 'info> {
+    /// Returns `None` for SOL offers, whose bid is held in the offer account's
+    /// own lamports. Token offers must supply the offer's escrow account.
+    fn load(
+        offer: &'a Account<'info, Offer>,
+        escrow_token_account: &'a Option<Account<'info, TokenAccount>>,
+        token_program: Option<AccountInfo<'info>>,
+    ) -> Result<Option<Self>> {
+        let mint = match offer.mint {
+            Some(mint) => mint,
+            None => return Ok(None),
+        };
+        
+        match (escrow_token_account, token_program) {
+            (Some(escrow_token_account), Some(token_program)) => {
+                require_keys_eq!(
+                    escrow_token_account.key(),
+                    get_associated_token_address(&offer.key(), &mint),
+                    ErrorCode::InvalidPaymentAccount
+                );
+                
+                Ok(Some(Self {
+                    offer,
+                    escrow_token_account,
+                    token_program,
+                    mint,
+                }))
+            }
+            _ => err!(ErrorCode::IncompleteTokenPayment),
+        }
+    }
+    
+    fn pay(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let offer = self.offer;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"offer",
+            offer.domain.as_ref(),
+            offer.bidder.as_ref(),
+            &[offer.bump],
+        ]];
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: self.escrow_token_account.to_account_info(),
+            to: to.clone(),
+            authority: offer.to_account_info(),
+        };
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(self.token_program.clone(), cpi_accounts, signer_seeds),
+            amount,
+        )
+    }
+    
+    /// Closes the emptied escrow account, returning its rent to `destination`.
+    fn close(&self, destination: &AccountInfo<'info>) -> Result<()> {
+        let offer = self.offer;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"offer",
+            offer.domain.as_ref(),
+            offer.bidder.as_ref(),
+            &[offer.bump],
+        ]];
+        let cpi_accounts = anchor_spl::token::CloseAccount {
+            account: self.escrow_token_account.to_account_info(),
+            destination: destination.clone(),
+            authority: offer.to_account_info(),
+        };
+        
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            self.token_program.clone(),
+            cpi_accounts,
+            signer_seeds,
+        ))
+    }
+}
+
+/// Returns an offer's bid to the bidder. Token bids are sent back from escrow
+/// here; SOL bids are returned when the offer account is closed to the bidder.
+fn refund_offer<'info>(
+    offer: &Account<'info, Offer>,
+    escrow_token_account: &Option<Account<'info, TokenAccount>>,
+    bidder_payment_account: &Option<Account<'info, TokenAccount>>,
+    bidder: &AccountInfo<'info>,
+    token_program: Option<AccountInfo<'info>>,
+) -> Result<()> {
+    if let Some(escrow) = OfferEscrow::load(offer, escrow_token_account, token_program)? {
+        let bidder_payment_account = bidder_payment_account
+            .as_ref()
+            .ok_or(ErrorCode::IncompleteTokenPayment)?;
+        require_keys_eq!(
+            bidder_payment_account.mint,
+            escrow.mint,
+            ErrorCode::InvalidPaymentAccount
+        );
+        require_keys_eq!(
+            bidder_payment_account.owner,
+            offer.bidder,
+            ErrorCode::InvalidPaymentAccount
+        );
+        
+        escrow.pay(&bidder_payment_account.to_account_info(), offer.amount)?;
+        escrow.close(bidder)?;
+    }
+    
+    emit!(OfferCancelled {
+        offer: offer.key(),
+        domain: offer.domain,
+        bidder: offer.bidder,
+    });
+    
+    Ok(())
+}
+
+/// Marketplace royalty owed on a sale, shared by the treasury and the TLD owner.
+fn sale_royalty(sale_price: u64) -> Result<u64> {
+    scale_price(sale_price, 10000, ROYALTY_PERCENTAGE as u64)
+}
+
+/// Moves an escrowed domain NFT to `to` and closes the escrow token account,
+/// returning its rent to `rent_destination`. `authority` is the PDA that owns
+/// the escrow, signing with `signer_seeds`.
+fn release_escrowed_nft<'info>(
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    escrow_token_account: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    rent_destination: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let cpi_accounts = anchor_spl::token::Transfer {
+        from: escrow_token_account.to_account_info(),
+        to: to.to_account_info(),
+        authority: authority.clone(),
+    };
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds),
+        1,
+    )?;
+    
+    let cpi_accounts = anchor_spl::token::CloseAccount {
+        account: escrow_token_account.to_account_info(),
+        destination: rent_destination.clone(),
+        authority: authority.clone(),
+    };
+    anchor_spl::token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    ))
+}
+
+/// Splits a fee or royalty between the treasury and the TLD owner,
+/// returning `(treasury_amount, tld_owner_amount)`.
+fn split_revenue(amount: u64, tld: &TLD) -> Result<(u64, u64)> {
+    let tld_owner_amount = scale_price(
+        amount,
+        MAX_REVENUE_SHARE_BPS as u64,
+        tld.owner_share_bps as u64,
+    )?;
+    Ok((amount - tld_owner_amount, tld_owner_amount))
+}
+
+/// Pays a fee or royalty in lamports from `payer`, split between the
+/// treasury and the TLD owner.
+fn pay_revenue<'info>(
+    payer: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    tld_owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    tld: &TLD,
+    amount: u64,
+) -> Result<()> {
+    let (treasury_amount, tld_owner_amount) = split_revenue(amount, tld)?;
+    
+    for (to, amount) in [(treasury, treasury_amount), (tld_owner, tld_owner_amount)] {
+        if amount == 0 {
+            continue;
+        }
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: to.clone(),
+                },
+            ),
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+/// Credits a royalty already debited from a program-owned escrow to the
+/// treasury and the TLD owner.
+fn credit_revenue(
+    treasury: &AccountInfo,
+    tld_owner: &AccountInfo,
+    tld: &TLD,
+    amount: u64,
+) -> Result<()> {
+    let (treasury_amount, tld_owner_amount) = split_revenue(amount, tld)?;
+    **treasury.try_borrow_mut_lamports()? += treasury_amount;
+    **tld_owner.try_borrow_mut_lamports()? += tld_owner_amount;
+    Ok(())
+}
+
+/// Converts `amount`, denominated against `from_unit`, into the same
+/// proportion of `to_unit`.
+fn scale_price(amount: u64, from_unit: u64, to_unit: u64) -> Result<u64> {
+    let scaled = (amount as u128)
+        .checked_mul(to_unit as u128)
+        .and_then(|value| value.checked_div(from_unit as u128))
+        .ok_or(ErrorCode::MathOverflow)?;
+    u64::try_from(scaled).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Converts a fee quoted in the TLD's pricing unit to lamports. USD-priced
+/// TLDs are quoted in cents and converted at the service's price feed.
+fn lamports_for_fee(
+    fee: u64,
+    tld: &TLD,
+    service: &Service,
+    price_feed: &Option<UncheckedAccount>,
+    now: i64,
+) -> Result<u64> {
+    if !tld.usd_pricing {
+        return Ok(fee);
+    }
+    
+    let price_feed = price_feed.as_ref().ok_or(ErrorCode::InvalidPriceFeed)?;
+    require_keys_eq!(price_feed.key(), service.price_feed, ErrorCode::InvalidPriceFeed);
+    
+    let feed = oracle::PriceFeed::load(price_feed)?;
+    feed.check(now, service.max_price_age, service.max_price_confidence_bps)?;
+    let lamports = feed.usd_cents_to_lamports(fee)?;
+    
+    msg!("Converted {} USD cents to {} lamports", fee, lamports);
+    Ok(lamports)
+}
+
+/// Seed for name-keyed PDAs; hashing keeps seeds within the 32-byte limit.
+pub fn name_hash(name: &str) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(validation::normalize(name).as_bytes()).to_bytes()
+}
+
+/// Deserializes an account of type `T` still in its originally deployed
+/// layout `L`. Accounts already in the current layout are rejected, since
+/// their data runs past the original fields.
+fn load_legacy<T: anchor_lang::Discriminator, L: AnchorDeserialize>(info: &AccountInfo) -> Result<L> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == T::DISCRIMINATOR,
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+    
+    let mut fields = &data[8..];
+    let legacy = L::deserialize(&mut fields)
+        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+    require!(fields.is_empty(), ErrorCode::AccountAlreadyMigrated);
+    Ok(legacy)
+}
+
+/// Deserializes `info` if it is an initialized account of this program,
+/// returning `None` for PDAs that have never been created.
+fn load_if_initialized<T: AccountDeserialize>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(T::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+}
+
+/// Resolves the per-year registration price for `name`: an explicit premium
+/// price wins, then the first length tier covering the name, then `base_price`.
+/// For premium names, also returns when the name was first made available.
+fn registration_price(
+    base_price: u64,
+    name: &str,
+    schedule_info: &AccountInfo,
+    premium_info: &AccountInfo,
+) -> Result<(u64, Option<i64>)> {
+    if let Some(premium_name) = load_if_initialized::<PremiumName>(premium_info)? {
+        msg!("Premium name price: {} lamports", premium_name.price);
+        return Ok((premium_name.price, Some(premium_name.released_at)));
+    }
+    
+    if let Some(schedule) = load_if_initialized::<PricingSchedule>(schedule_info)? {
+        if let Some(tier) = schedule
+            .tiers
+            .iter()
+            .find(|tier| name.len() <= tier.max_length as usize)
+        {
+            return Ok((tier.price, None));
+        }
+    }
+    
+    Ok((base_price, None))
+}
+
+/// One-off Dutch auction premium for a name released at `released_at`: the
+/// first year starts at the TLD's release start price and decays linearly to
+/// the name's regular `price_per_year` over the TLD's release period.
+fn release_auction_premium(tld: &TLD, price_per_year: u64, released_at: i64, now: i64) -> Result<u64> {
+    let elapsed = now.saturating_sub(released_at).max(0);
+    if tld.release_period == 0
+        || tld.release_start_price <= price_per_year
+        || elapsed >= tld.release_period
+    {
+        return Ok(0);
+    }
+    
+    let premium = scale_price(
+        tld.release_start_price - price_per_year,
+        tld.release_period as u64,
+        (tld.release_period - elapsed) as u64,
+    )?;
+    
+    msg!("Release auction premium: {}", premium);
+    Ok(premium)
+}
+
+/// Returns `base` extended by `years`, rejecting periods that would leave more
+/// than `max_years` prepaid from `now`.
+fn extend_expiry(base: i64, now: i64, years: u8, max_years: u8) -> Result<i64> {
+    require!(years > 0, ErrorCode::InvalidYears);
+    
+    let new_expires_at = (years as i64)
+        .checked_mul(SECONDS_PER_YEAR)
+        .and_then(|period| base.checked_add(period))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        new_expires_at - now <= max_years as i64 * SECONDS_PER_YEAR,
+        ErrorCode::PrepaidPeriodTooLong
+    );
+    
+    Ok(new_expires_at)
+}
+
+/// Clears `primary_info` if it is an initialized primary name pointing at `domain`.
+/// Missing (never created) primary name accounts are left untouched.
+fn clear_primary_domain_if_matches(primary_info: &AccountInfo, domain: Pubkey) -> Result<()> {
+    let mut primary = match load_if_initialized::<PrimaryName>(primary_info)? {
+        Some(primary) if primary.domain == domain => primary,
+        _ => return Ok(()),
+    };
+    
+    primary.domain = Pubkey::default();
+    primary.try_serialize(&mut &mut primary_info.try_borrow_mut_data()?[..])?;
+    
+    msg!("Primary domain cleared for {}", primary.owner);
+    
+    emit!(PrimaryDomainCleared {
+        owner: primary.owner,
+        domain,
+    });
+    
+    Ok(())
+}
+
+// Account structures
+#[account]
+pub struct Service {
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub total_domains: u64,
+    pub total_tlds: u64,
+    pub grace_period: i64, // Seconds after expiry during which only renewal is allowed
+    pub max_prepaid_years: u8,
+    pub price_feed: Pubkey, // Pyth-compatible SOL/USD price account for USD-priced TLDs
+    pub max_price_age: i64, // Seconds
+    pub max_price_confidence_bps: u16,
+    pub pending_authority: Option<Pubkey>, // Proposed authority awaiting acceptance
+    pub pending_treasury: Option<Pubkey>, // Proposed treasury awaiting acceptance
+    pub guardian: Pubkey, // Can pause and unpause alongside the authority (default when unset)
+    pub paused: u8, // Bitmask of PAUSE_* flags
+}
+
+impl Service {
+    pub const SPACE: usize =
+        8 + 32 + 32 + 8 + 8 + 8 + 1 + 32 + 8 + 2 + (1 + 32) + (1 + 32) + 32 + 1;
+    
+    /// Fails if any of the operations in `flags` are paused.
+    pub fn check_not_paused(&self, flags: u8) -> Result<()> {
+        require!(self.paused & flags == 0, ErrorCode::ServicePaused);
+        Ok(())
+    }
+    
+    /// The authority, the guardian if one is set, and guardian role holders
+    /// control the pause flags.
+    pub fn check_pauser(&self, signer: Pubkey, role: &Option<Account<Role>>) -> Result<()> {
+        if self.guardian != Pubkey::default() && signer == self.guardian {
+            return Ok(());
+        }
+        self.check_role(signer, role, RoleKind::Guardian)
+    }
+    
+    /// The authority holds every role. Anyone else needs a granted `Role`
+    /// account of the required kind or an admin role.
+    pub fn has_role(&self, signer: Pubkey, role: &Option<Account<Role>>, required: RoleKind) -> bool {
+        signer == self.authority
+            || matches!(
+                role,
+                Some(role) if role.holder == signer
+                    && (role.kind == required || role.kind == RoleKind::Admin)
+            )
+    }
+    
+    pub fn check_role(
+        &self,
+        signer: Pubkey,
+        role: &Option<Account<Role>>,
+        required: RoleKind,
+    ) -> Result<()> {
+        require!(self.has_role(signer, role, required), ErrorCode::Unauthorized);
+        Ok(())
+    }
+}
+
+#[account]
+pub struct TLD {
+    pub name: String,
+    pub price: u64,
+    pub owner: Pubkey,
+    pub created_at: i64,
+    pub is_active: bool,
+    pub total_domains: u64,
+    pub renewal_price: u64,
+    pub allow_idna: bool, // Accept punycode (xn--) domain names
+    pub usd_pricing: bool, // Prices are USD cents, converted to lamports at the price feed
+    pub release_start_price: u64, // Per-year price a released name starts at in its Dutch auction
+    pub release_period: i64, // Seconds for the release price to decay to the regular price (0 disables)
+    pub owner_share_bps: u16, // Share of fees and royalties paid to the TLD owner, in basis points
+}
+
+impl TLD {
+    pub fn space(name_len: usize) -> usize {
+        8 + 4 + name_len + 8 + 32 + 8 + 1 + 8 + 8 + 1 + 1 + 8 + 8 + 2
+    }
+    
+    pub fn initialize(
+        &mut self,
+        name: String,
+        price: u64,
+        renewal_price: u64,
+        allow_idna: bool,
+        owner: Pubkey,
+        now: i64,
+    ) {
+        self.name = name;
+        self.price = price;
+        self.owner = owner;
+        self.created_at = now;
+        self.is_active = true;
+        self.total_domains = 0;
+        self.renewal_price = renewal_price;
+        self.allow_idna = allow_idna;
+        self.usd_pricing = false;
+        self.release_start_price = 0;
+        self.release_period = 0;
+        self.owner_share_bps = 0;
+    }
+    
+    /// The TLD's owner manages its pricing and names, as do the service
+    /// authority and holders of the `required` role.
+    pub fn check_manager(
+        &self,
+        signer: Pubkey,
+        service: &Service,
+        role: &Option<Account<Role>>,
+        required: RoleKind,
+    ) -> Result<()> {
+        if signer == self.owner {
+            return Ok(());
+        }
+        service.check_role(signer, role, required)
+    }
+}
+
+// Service and TLD layouts as originally deployed, before the fields above were
+// added. Accounts still in these layouts are upgraded with migrate_service and
+// migrate_tld.
+#[derive(AnchorDeserialize)]
+struct LegacyService {
+    authority: Pubkey,
+    treasury: Pubkey,
+    total_domains: u64,
+    total_tlds: u64,
+}
+
+#[derive(AnchorDeserialize)]
+struct LegacyTLD {
+    name: String,
+    price: u64,
+    owner: Pubkey,
+    created_at: i64,
+    is_active: bool,
+    total_domains: u64,
+}
+
+#[account]
+pub struct Domain {
+    pub name: String,
+    pub tld: String,
+    pub owner: Pubkey,
+    pub registered_at: i64,
+    pub expires_at: i64,
+    pub is_active: bool,
+    pub mint: Pubkey,  // NFT mint address
+}
+
+impl Domain {
+    /// Fails once the domain has expired, distinguishing the grace period
+    /// (renewal still possible) from full expiry.
+    pub fn check_not_expired(&self, now: i64, grace_period: i64) -> Result<()> {
+        if now < self.expires_at {
+            return Ok(());
+        }
+        require!(
+            now >= self.expires_at.saturating_add(grace_period),
+            ErrorCode::DomainInGracePeriod
+        );
+        err!(ErrorCode::DomainExpired)
+    }
+
+    /// Passes only once the grace period has passed and the name can be re-registered.
+    pub fn check_reclaimable(&self, now: i64, grace_period: i64) -> Result<()> {
+        require!(
+            now >= self.expires_at.saturating_add(grace_period),
+            ErrorCode::DomainNotExpired
+        );
+        Ok(())
+    }
+
+    /// Renewal stays allowed until the grace period has passed.
+    pub fn check_renewable(&self, now: i64, grace_period: i64) -> Result<()> {
+        require!(
+            now < self.expires_at.saturating_add(grace_period),
+            ErrorCode::DomainExpired
+        );
+        Ok(())
+    }
+}
+
+// Records last updated before their domain's `registered_at` belong to a
+// previous registrant and should be ignored by resolvers.
+#[account]
+pub struct Record {
+    pub domain: Pubkey,
+    pub record_type: RecordType,
+    pub data: Vec<u8>,
+    pub updated_at: i64,
+}
+
+impl Record {
+    pub fn space(data_len: usize) -> usize {
+        8 + 32 + 1 + 4 + data_len + 8
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordType {
+    A,       // IPv4 address, 4 bytes
+    AAAA,    // IPv6 address, 16 bytes
+    CNAME,   // Canonical name (UTF-8)
+    TXT,     // Free-form text (UTF-8)
+    MX,      // 2-byte little-endian preference followed by a host name (UTF-8)
+    SOL,     // Solana wallet address, 32 bytes
+    IPFS,    // IPFS content hash (UTF-8 CID)
+    ARWEAVE, // Arweave transaction ID (UTF-8)
+    URL,     // URL (UTF-8)
+}
+
+impl RecordType {
+    pub fn validate(&self, data: &[u8]) -> Result<()> {
+        let is_valid = match self {
+            RecordType::A => data.len() == 4,
+            RecordType::AAAA => data.len() == 16,
+            RecordType::SOL => data.len() == 32,
+            RecordType::MX => data.len() > 2 && std::str::from_utf8(&data[2..]).is_ok(),
+            RecordType::CNAME
+            | RecordType::TXT
+            | RecordType::IPFS
+            | RecordType::ARWEAVE
+            | RecordType::URL => !data.is_empty() && std::str::from_utf8(data).is_ok(),
+        };
+        require!(is_valid, ErrorCode::InvalidRecordData);
+        Ok(())
+    }
+}
+
+#[account]
+pub struct PricingSchedule {
+    pub tld: Pubkey,
+    pub tiers: Vec<PriceTier>, // Ordered by increasing max_length
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriceTier {
+    pub max_length: u8, // Longest name (in characters) this tier applies to
+    pub price: u64,     // Registration price per year
+}
+
+#[account]
+pub struct PremiumName {
+    pub tld: Pubkey,
+    pub name: String,
+    pub price: u64, // Registration price per year
+    pub released_at: i64, // When the name was first made available, starting its release auction
+}
+
+// Names kept out of public registration. Only the TLD's managers can assign
+// them, and the reservation is consumed when they do.
+#[account]
+pub struct ReservedName {
+    pub tld: Pubkey,
+    pub name: String,
+    pub reserved_by: Pubkey,
+    pub created_at: i64,
+}
+
+#[account]
+pub struct TokenPrice {
+    pub tld: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,         // Registration price per year, in token base units
+    pub renewal_price: u64, // Renewal price per year, in token base units
+}
+
+#[account]
+pub struct PrimaryName {
+    pub owner: Pubkey,
+    pub domain: Pubkey, // Pubkey::default() when cleared
+}
+
+#[account]
+pub struct SubdomainConfig {
+    pub parent: Pubkey,
+    pub fee: u64,      // Paid to the parent owner by self-issuing members
+    pub is_open: bool, // Whether anyone may issue themselves a subdomain
+}
+
+#[account]
+pub struct Subdomain {
+    pub parent: Pubkey,
+    pub name: String,
+    pub owner: Pubkey,
+    pub created_at: i64,
+    pub is_revocable: bool,
+}
+
+// While listed, the domain NFT is escrowed in the listing's token account and
+// the domain's owner is the listing PDA; the seller gets both back on cancel.
+#[account]
+pub struct Listing {
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64, // Lamports
+    pub created_at: i64,
+}
+
+// SOL offers hold the bid in the offer account's own lamports; token offers
+// hold it in the offer PDA's associated token account for `mint`.
+#[account]
+pub struct Offer {
+    pub domain: Pubkey,
+    pub bidder: Pubkey,
+    pub mint: Option<Pubkey>, // None for SOL
+    pub amount: u64,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+// While an auction runs, the domain NFT is escrowed in the auction's token
+// account, the domain's owner is the auction PDA, and bids are held in the
+// auction account's own lamports until it is settled.
+#[account]
+pub struct Auction {
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub reserve_price: u64, // Lamports
+    pub min_increment: u64,
+    pub end_time: i64,
+    pub highest_bidder: Option<Pubkey>,
+    pub highest_bid: u64,
+    pub bump: u8,
+}
+
+// Pending request for a new TLD. The deposit is held in the account's own
+// lamports until the authority approves or rejects it.
+#[account]
+pub struct TldApplication {
+    pub applicant: Pubkey,
+    pub name: String,
+    pub price: u64,
+    pub renewal_price: u64,
+    pub allow_idna: bool,
+    pub deposit: u64, // Lamports
+    pub created_at: i64,
+}
+
+// Grants `holder` one operator role. Admins can do everything the other roles
+// can, and the authority implicitly holds every role.
+#[account]
+pub struct Role {
+    pub holder: Pubkey,
+    pub kind: RoleKind,
+    pub granted_by: Pubkey,
+    pub granted_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RoleKind {
+    Admin,          // Service settings, and granting and revoking the other roles
+    PricingManager, // TLD prices, release auctions, premium names and token prices
+    TldManager,     // TLD creation, applications, status and configuration
+    Operator,       // Reserved names and fee-free registrations for support
+    Guardian,       // Pausing and unpausing the service
+}
+
+// Context structures
+#[derive(Accounts)]
+pub struct InitializeService<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Service::SPACE,
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateTLD<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = TLD::space(name.len()),
+        seeds = [b"tld", validation::normalize(&name).as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SubmitTldApplication<'info> {
+    #[account(
+        init,
+        payer = applicant,
+        space = 8 + 32 + 4 + name.len() + 8 + 8 + 1 + 8 + 8,
+        seeds = [b"tld_application", validation::normalize(&name).as_bytes()],
+        bump
+    )]
+    pub application: Account<'info, TldApplication>,
+    /// CHECK: TLD PDA for the name, which must not exist yet
+    #[account(
+        seeds = [b"tld", validation::normalize(&name).as_bytes()],
+        bump
+    )]
+    pub tld: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveTldApplication<'info> {
+    #[account(mut, has_one = applicant, close = applicant)]
+    pub application: Account<'info, TldApplication>,
+    #[account(
+        init,
+        payer = authority,
+        space = TLD::space(application.name.len()),
+        seeds = [b"tld", application.name.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    /// CHECK: Applicant wallet receiving the application rent, checked by has_one
+    #[account(mut)]
+    pub applicant: AccountInfo<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+    /// CHECK: Treasury account receiving the application deposit
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectTldApplication<'info> {
+    #[account(mut, has_one = applicant, close = applicant)]
+    pub application: Account<'info, TldApplication>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    /// CHECK: Applicant wallet receiving the refund, checked by has_one
+    #[account(mut)]
+    pub applicant: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTldApplication<'info> {
+    #[account(mut, has_one = applicant, close = applicant)]
+    pub application: Account<'info, TldApplication>,
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTLDPricing<'info> {
+    #[account(mut)]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReleaseAuction<'info> {
+    #[account(mut)]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTLDRevenueShare<'info> {
+    #[account(mut)]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTLDStatus<'info> {
+    #[account(mut)]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct TransferTLDOwnership<'info> {
+    #[account(mut)]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTLDConfig<'info> {
+    #[account(mut)]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+#[instruction(domain_name: String, tld_name: String)]
+pub struct RegisterDomainNFT<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + 4 + domain_name.len() + 4 + tld_name.len() + 32 + 8 + 8 + 1 + 32,
+        seeds = [
+            b"domain",
+            validation::normalize(&domain_name).as_bytes(),
+            validation::normalize(&tld_name).as_bytes(),
+        ],
+        bump
+    )]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        mut,
+        seeds = [b"tld", validation::normalize(&tld_name).as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    /// CHECK: TLD pricing schedule PDA, which may not exist
+    #[account(
+        seeds = [b"pricing", tld.key().as_ref()],
+        bump
+    )]
+    pub pricing_schedule: UncheckedAccount<'info>,
+    /// CHECK: Premium price PDA for this name, which may not exist
+    #[account(
+        seeds = [b"premium", tld.key().as_ref(), &name_hash(&domain_name)],
+        bump
+    )]
+    pub premium_name: UncheckedAccount<'info>,
+    /// CHECK: Reserved name PDA for this name, which must not exist
+    #[account(
+        seeds = [b"reserved", tld.key().as_ref(), &name_hash(&domain_name)],
+        bump
+    )]
+    pub reserved_name: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub owner_role: Option<Account<'info, Role>>,
+    /// CHECK: Treasury wallet for fee collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    
+    // NFT-related accounts
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = owner,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+    /// CHECK: Metadata account
+    #[account(mut)]
+    pub metadata: AccountInfo<'info>,
+    
+    /// CHECK: SOL/USD price feed for USD-priced TLDs, verified against service.price_feed
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    // Optional SPL token payment accounts (fees are paid in SOL when omitted)
+    pub token_price: Option<Account<'info, TokenPrice>>,
+    #[account(mut)]
+    pub payer_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub tld_owner_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, MetadataProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RenewDomain<'info> {
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub owner_role: Option<Account<'info, Role>>,
+    /// CHECK: Treasury wallet for fee collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    
+    /// CHECK: SOL/USD price feed for USD-priced TLDs, verified against service.price_feed
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    // Optional SPL token payment accounts (fees are paid in SOL when omitted)
+    pub token_price: Option<Account<'info, TokenPrice>>,
+    #[account(mut)]
+    pub payer_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub tld_owner_payment_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferDomainNFT<'info> {
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub current_owner: Signer<'info>,
+    /// CHECK: Seller's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", current_owner.key().as_ref()],
+        bump
+    )]
+    pub current_owner_primary: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+    /// CHECK: Treasury wallet for royalty collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    
+    // NFT token accounts
+    #[account(address = domain.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = current_owner,
+    )]
+    pub current_owner_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = new_owner,
+        associated_token::mint = mint,
+        associated_token::authority = new_owner,
+    )]
+    pub new_owner_token_account: Account<'info, TokenAccount>,
+    
+    // Optional SPL token payment accounts (fees are paid in SOL when omitted)
+    pub token_price: Option<Account<'info, TokenPrice>>,
+    #[account(mut)]
+    pub payer_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub tld_owner_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GiftDomainNFT<'info> {
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub current_owner: Signer<'info>,
+    /// CHECK: Giver's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", current_owner.key().as_ref()],
+        bump
+    )]
+    pub current_owner_primary: UncheckedAccount<'info>,
+    /// CHECK: Recipient wallet
+    pub new_owner: AccountInfo<'info>,
+    
+    // NFT token accounts
+    #[account(address = domain.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = current_owner,
+    )]
+    pub current_owner_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = current_owner,
+        associated_token::mint = mint,
+        associated_token::authority = new_owner,
+    )]
+    pub new_owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpiredDomainNFT<'info> {
+    #[account(
+        mut,
+        seeds = [b"domain", domain.name.as_bytes(), domain.tld.as_bytes()],
+        bump
+    )]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    /// CHECK: TLD pricing schedule PDA, which may not exist
+    #[account(
+        seeds = [b"pricing", tld.key().as_ref()],
+        bump
+    )]
+    pub pricing_schedule: UncheckedAccount<'info>,
+    /// CHECK: Premium price PDA for this name, which may not exist
+    #[account(
+        seeds = [b"premium", tld.key().as_ref(), &name_hash(&domain.name)],
+        bump
+    )]
+    pub premium_name: UncheckedAccount<'info>,
+    /// CHECK: Reserved name PDA for this name, which must not exist
+    #[account(
+        seeds = [b"reserved", tld.key().as_ref(), &name_hash(&domain.name)],
+        bump
+    )]
+    pub reserved_name: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    /// CHECK: Previous owner's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", domain.owner.as_ref()],
+        bump
+    )]
+    pub previous_owner_primary: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+    pub new_owner_role: Option<Account<'info, Role>>,
+    /// CHECK: Treasury wallet for fee collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    
+    // NFT-related accounts
+    #[account(
+        init,
+        payer = new_owner,
+        mint::decimals = 0,
+        mint::authority = new_owner,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = new_owner,
+        associated_token::mint = mint,
+        associated_token::authority = new_owner,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+    /// CHECK: Metadata account
+    #[account(mut)]
+    pub metadata: AccountInfo<'info>,
+    
+    /// CHECK: SOL/USD price feed for USD-priced TLDs, verified against service.price_feed
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    // Optional SPL token payment accounts (fees are paid in SOL when omitted)
+    pub token_price: Option<Account<'info, TokenPrice>>,
+    #[account(mut)]
+    pub payer_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub tld_owner_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, MetadataProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ListDomainNFT<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + 32 + 32 + 32 + 8 + 8,
+        seeds = [b"listing", domain.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(
+        address = domain.mint,
+        constraint = is_locked_domain_mint(&mint) @ ErrorCode::DomainMintNotLocked,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListingNFT<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", domain.key().as_ref()],
+        bump,
+        has_one = domain,
+        has_one = seller,
+        has_one = mint,
+        close = seller
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Seller wallet receiving the NFT and rent back, checked by has_one
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyListingNFT<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", domain.key().as_ref()],
+        bump,
+        has_one = domain,
+        has_one = seller,
+        has_one = mint,
+        close = seller
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Seller wallet receiving the sale proceeds, checked by has_one
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+    /// CHECK: Seller's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_primary: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Treasury wallet for royalty collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + 32 + 32 + (1 + 32) + 8 + 8 + 8 + 1,
+        seeds = [b"offer", domain.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, Offer>,
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    
+    // Optional SPL token bid accounts (bids are in SOL when omitted)
+    pub token_price: Option<Account<'info, TokenPrice>>,
+    #[account(mut)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOfferNFT<'info> {
+    #[account(
+        mut,
+        has_one = domain,
+        has_one = bidder,
+        close = bidder
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// CHECK: Seller's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_primary: UncheckedAccount<'info>,
+    /// CHECK: Bidder wallet receiving the domain and the offer rent, checked by has_one
+    #[account(mut)]
+    pub bidder: AccountInfo<'info>,
+    /// CHECK: Treasury wallet for royalty collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    
+    // NFT token accounts
+    #[account(
+        address = domain.mint,
+        constraint = is_locked_domain_mint(&mint) @ ErrorCode::DomainMintNotLocked,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = bidder,
+    )]
+    pub bidder_token_account: Account<'info, TokenAccount>,
+    
+    // Token offer accounts (unused for SOL offers)
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub tld_owner_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    #[account(mut, has_one = bidder, close = bidder)]
+    pub offer: Account<'info, Offer>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    
+    // Token offer accounts (unused for SOL offers)
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpiredOffer<'info> {
+    #[account(mut, has_one = bidder, close = bidder)]
+    pub offer: Account<'info, Offer>,
+    /// CHECK: Bidder wallet receiving the refund, checked by has_one
+    #[account(mut)]
+    pub bidder: AccountInfo<'info>,
+    
+    // Token offer accounts (unused for SOL offers)
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuctionNFT<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + (1 + 32) + 8 + 1,
+        seeds = [b"auction", domain.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(
+        address = domain.mint,
+        constraint = is_locked_domain_mint(&mint) @ ErrorCode::DomainMintNotLocked,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = auction,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut, has_one = domain)]
+    pub auction: Account<'info, Auction>,
+    pub domain: Account<'info, Domain>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// CHECK: Current high bidder receiving their refund, checked in the handler
+    #[account(mut)]
+    pub previous_bidder: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuctionNFT<'info> {
+    #[account(
+        mut,
+        has_one = domain,
+        has_one = seller,
+        has_one = mint,
+        close = seller
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"tld", domain.tld.as_bytes()],
+        bump
+    )]
+    pub tld: Account<'info, TLD>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    /// CHECK: Seller wallet receiving the winning bid, checked by has_one
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+    /// CHECK: Seller's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_primary: UncheckedAccount<'info>,
+    /// CHECK: High bidder, or the seller when there were no bids; checked in the handler
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    /// CHECK: Treasury wallet for royalty collection
+    #[account(mut, address = service.treasury)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: TLD owner receiving its share of fees and royalties, checked by address
+    #[account(mut, address = tld.owner)]
+    pub tld_owner: AccountInfo<'info>,
+    
+    // NFT token accounts (the mint is burned from escrow if the domain was reclaimed)
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = auction,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
-// Account structures
-#[account]
-pub struct Service {
-    pub authority: Pubkey,
-    pub treasury: Pubkey,
-    pub total_domains: u64,
-    pub total_tlds: u64,
+#[derive(Accounts)]
+pub struct CancelAuctionNFT<'info> {
+    #[account(
+        mut,
+        has_one = domain,
+        has_one = seller,
+        has_one = mint,
+        close = seller
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub domain: Account<'info, Domain>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = auction,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
-#[account]
-pub struct TLD {
-    pub name: String,
-    pub price: u64,
-    pub owner: Pubkey,
-    pub created_at: i64,
-    pub is_active: bool,
-    pub total_domains: u64,
+#[derive(Accounts)]
+#[instruction(record_type: RecordType, data: Vec<u8>)]
+pub struct SetRecord<'info> {
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Record::space(data.len()),
+        seeds = [b"record", domain.key().as_ref(), &[record_type as u8]],
+        bump
+    )]
+    pub record: Account<'info, Record>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[account]
-pub struct Domain {
-    pub name: String,
-    pub tld: String,
-    pub owner: Pubkey,
-    pub registered_at: i64,
-    pub expires_at: i64,
-    pub is_active: bool,
-    pub mint: Pubkey,  // NFT mint address
+#[derive(Accounts)]
+pub struct DeleteRecord<'info> {
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        mut,
+        has_one = domain,
+        close = owner
+    )]
+    pub record: Account<'info, Record>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
 }
 
-// Context structures
 #[derive(Accounts)]
-pub struct InitializeService<'info> {
+pub struct SetPrimaryDomain<'info> {
+    pub domain: Account<'info, Domain>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 32 + 32,
+        seeds = [b"primary", owner.key().as_ref()],
+        bump
+    )]
+    pub primary: Account<'info, PrimaryName>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearPrimaryDomain<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"primary", owner.key().as_ref()],
+        bump
+    )]
+    pub primary: Account<'info, PrimaryName>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureSubdomains<'info> {
+    pub parent: Account<'info, Domain>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"subdomain_config", parent.key().as_ref()],
+        bump
+    )]
+    pub config: Account<'info, SubdomainConfig>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateSubdomain<'info> {
+    pub parent: Account<'info, Domain>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        seeds = [b"subdomain_config", parent.key().as_ref()],
+        bump
+    )]
+    pub config: Option<Account<'info, SubdomainConfig>>,
     #[account(
         init,
+        payer = creator,
+        space = 8 + 32 + 4 + name.len() + 32 + 8 + 1,
+        seeds = [
+            b"subdomain",
+            parent.key().as_ref(),
+            validation::normalize(&name).as_bytes(),
+        ],
+        bump
+    )]
+    pub subdomain: Account<'info, Subdomain>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// CHECK: Parent domain owner receiving subdomain fees
+    #[account(mut)]
+    pub parent_owner: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSubdomain<'info> {
+    pub parent: Account<'info, Domain>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        mut,
+        has_one = parent,
+        close = parent_owner
+    )]
+    pub subdomain: Account<'info, Subdomain>,
+    #[account(mut)]
+    pub parent_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseSubdomain<'info> {
+    #[account(mut, close = owner)]
+    pub subdomain: Account<'info, Subdomain>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTokenPrice<'info> {
+    pub tld: Account<'info, TLD>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
         payer = authority,
         space = 8 + 32 + 32 + 8 + 8,
+        seeds = [b"token_price", tld.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub token_price: Account<'info, TokenPrice>,
+    #[account(
         seeds = [b"service"],
         bump
     )]
     pub service: Account<'info, Service>,
     #[account(mut)]
     pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(name: String)]
-pub struct CreateTLD<'info> {
+pub struct RemoveTokenPrice<'info> {
+    pub tld: Account<'info, TLD>,
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 4 + name.len() + 8 + 32 + 8 + 1 + 8,
-        seeds = [b"tld", name.as_bytes()],
+        mut,
+        has_one = tld,
+        close = authority
+    )]
+    pub token_price: Account<'info, TokenPrice>,
+    #[account(
+        seeds = [b"service"],
         bump
     )]
+    pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPricingSchedule<'info> {
     pub tld: Account<'info, TLD>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 4 + MAX_PRICE_TIERS * (1 + 8),
+        seeds = [b"pricing", tld.key().as_ref()],
+        bump
+    )]
+    pub pricing_schedule: Account<'info, PricingSchedule>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
+    pub service: Account<'info, Service>,
     #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetPremiumName<'info> {
+    pub tld: Account<'info, TLD>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 4 + name.len() + 8 + 8,
+        seeds = [b"premium", tld.key().as_ref(), &name_hash(&name)],
+        bump
+    )]
+    pub premium_name: Account<'info, PremiumName>,
+    #[account(
+        seeds = [b"service"],
+        bump
+    )]
     pub service: Account<'info, Service>,
     #[account(mut)]
     pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(domain_name: String, tld_name: String)]
-pub struct RegisterDomainNFT<'info> {
+pub struct RemovePremiumName<'info> {
+    pub tld: Account<'info, TLD>,
     #[account(
-        init,
-        payer = owner,
-        space = 8 + 4 + domain_name.len() + 4 + tld_name.len() + 32 + 8 + 8 + 1 + 32,
-        seeds = [b"domain", domain_name.as_bytes(), tld_name.as_bytes()],
+        mut,
+        has_one = tld,
+        close = authority
+    )]
+    pub premium_name: Account<'info, PremiumName>,
+    #[account(
+        seeds = [b"service"],
         bump
     )]
-    pub domain: Account<'info, Domain>,
+    pub service: Account<'info, Service>,
     #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ReserveName<'info> {
     pub tld: Account<'info, TLD>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 4 + name.len() + 32 + 8,
+        seeds = [b"reserved", tld.key().as_ref(), &name_hash(&name)],
+        bump
+    )]
+    pub reserved_name: Account<'info, ReservedName>,
+    #[account(seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
     #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnreserveName<'info> {
+    pub tld: Account<'info, TLD>,
+    #[account(
+        mut,
+        has_one = tld,
+        close = authority
+    )]
+    pub reserved_name: Account<'info, ReservedName>,
+    #[account(seeds = [b"service"], bump)]
     pub service: Account<'info, Service>,
     #[account(mut)]
-    pub owner: Signer<'info>,
-    /// CHECK: Treasury wallet for fee collection
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct AssignReservedNameNFT<'info> {
     #[account(mut)]
-    pub treasury: AccountInfo<'info>,
+    pub tld: Account<'info, TLD>,
+    #[account(
+        mut,
+        has_one = tld,
+        close = authority,
+        seeds = [b"reserved", tld.key().as_ref(), &name_hash(&name)],
+        bump
+    )]
+    pub reserved_name: Account<'info, ReservedName>,
+    // Exists already when the name was reserved after being registered
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 4 + name.len() + 4 + tld.name.len() + 32 + 8 + 8 + 1 + 32,
+        seeds = [
+            b"domain",
+            validation::normalize(&name).as_bytes(),
+            tld.name.as_bytes(),
+        ],
+        bump
+    )]
+    pub domain: Account<'info, Domain>,
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    /// CHECK: Previous owner's primary name PDA, which may not exist
+    #[account(
+        mut,
+        seeds = [b"primary", domain.owner.as_ref()],
+        bump
+    )]
+    pub previous_owner_primary: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+    /// CHECK: Wallet receiving the reserved name
+    pub recipient: AccountInfo<'info>,
     
     // NFT-related accounts
     #[account(
         init,
-        payer = owner,
+        payer = authority,
         mint::decimals = 0,
-        mint::authority = owner,
-        mint::freeze_authority = owner,
+        mint::authority = authority,
     )]
     pub mint: Account<'info, Mint>,
     #[account(
         init,
-        payer = owner,
+        payer = authority,
         associated_token::mint = mint,
-        associated_token::authority = owner,
+        associated_token::authority = recipient,
     )]
     pub token_account: Account<'info, TokenAccount>,
     /// CHECK: Metadata account
@@ -447,57 +4939,151 @@ pub struct RegisterDomainNFT<'info> {
 }
 
 #[derive(Accounts)]
-pub struct RenewDomain<'info> {
-    #[account(mut)]
-    pub domain: Account<'info, Domain>,
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
     pub service: Account<'info, Service>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// CHECK: Treasury wallet for fee collection
-    #[account(mut)]
-    pub treasury: AccountInfo<'info>,
-    pub system_program: Program<'info, System>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct TransferDomainNFT<'info> {
-    #[account(mut)]
-    pub domain: Account<'info, Domain>,
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityProposal<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGracePeriod<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMaxPrepaidYears<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeTreasury<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptTreasury<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub new_treasury: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTreasuryProposal<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGuardian<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut, seeds = [b"service"], bump)]
+    pub service: Account<'info, Service>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey, kind: RoleKind)]
+pub struct GrantRole<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 32 + 8,
+        seeds = [b"role", holder.as_ref(), &[kind as u8]],
+        bump
+    )]
+    pub role: Account<'info, Role>,
+    #[account(seeds = [b"service"], bump)]
     pub service: Account<'info, Service>,
     #[account(mut)]
-    pub current_owner: Signer<'info>,
-    /// CHECK: New owner wallet
-    #[account(mut)]
-    pub new_owner: AccountInfo<'info>,
-    /// CHECK: Treasury wallet for royalty collection
-    #[account(mut)]
-    pub treasury: AccountInfo<'info>,
-    
-    // NFT token accounts
-    #[account(mut)]
-    pub current_owner_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub new_owner_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
+    pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateAuthority<'info> {
-    #[account(mut)]
+pub struct RevokeRole<'info> {
+    #[account(mut, close = authority)]
+    pub role: Account<'info, Role>,
+    #[account(seeds = [b"service"], bump)]
     pub service: Account<'info, Service>,
+    #[account(mut)]
     pub authority: Signer<'info>,
+    pub authority_role: Option<Account<'info, Role>>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateTreasury<'info> {
+pub struct MigrateService<'info> {
+    /// CHECK: Service in its original layout, which no longer deserializes; decoded in the handler
+    #[account(mut, seeds = [b"service"], bump, owner = crate::ID)]
+    pub service: UncheckedAccount<'info>,
     #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTLD<'info> {
+    /// CHECK: TLD in its original layout, decoded and checked against its name's PDA in the handler
+    #[account(mut, owner = crate::ID)]
+    pub tld: UncheckedAccount<'info>,
+    #[account(seeds = [b"service"], bump)]
     pub service: Account<'info, Service>,
+    #[account(mut)]
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 // Events
+#[event]
+pub struct AuthorityProposed {
+    pub current_authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityProposalCancelled {
+    pub current_authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
 #[event]
 pub struct AuthorityUpdated {
     pub old_authority: Pubkey,
@@ -510,6 +5096,332 @@ pub struct TreasuryUpdated {
     pub new_treasury: Pubkey,
 }
 
+#[event]
+pub struct TreasuryProposed {
+    pub current_treasury: Pubkey,
+    pub pending_treasury: Pubkey,
+}
+
+#[event]
+pub struct TreasuryProposalCancelled {
+    pub current_treasury: Pubkey,
+    pub pending_treasury: Pubkey,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+}
+
+#[event]
+pub struct ServicePauseUpdated {
+    pub previous_paused: u8,
+    pub paused: u8,
+    pub updated_by: Pubkey,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub holder: Pubkey,
+    pub kind: RoleKind,
+    pub granted_by: Pubkey,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub holder: Pubkey,
+    pub kind: RoleKind,
+    pub revoked_by: Pubkey,
+}
+
+#[event]
+pub struct GracePeriodUpdated {
+    pub old_grace_period: i64,
+    pub new_grace_period: i64,
+}
+
+#[event]
+pub struct MaxPrepaidYearsUpdated {
+    pub old_max_prepaid_years: u8,
+    pub new_max_prepaid_years: u8,
+}
+
+#[event]
+pub struct PriceFeedUpdated {
+    pub price_feed: Pubkey,
+    pub max_price_age: i64,
+    pub max_price_confidence_bps: u16,
+}
+
+#[event]
+pub struct TLDPricingUpdated {
+    pub tld: Pubkey,
+    pub old_price: u64,
+    pub new_price: u64,
+    pub old_renewal_price: u64,
+    pub new_renewal_price: u64,
+    pub usd_pricing: bool,
+}
+
+#[event]
+pub struct TLDDeactivated {
+    pub tld: Pubkey,
+}
+
+#[event]
+pub struct TLDReactivated {
+    pub tld: Pubkey,
+}
+
+#[event]
+pub struct TLDOwnershipTransferred {
+    pub tld: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct TLDConfigUpdated {
+    pub tld: Pubkey,
+    pub allow_idna: bool,
+}
+
+#[event]
+pub struct TldApplicationSubmitted {
+    pub application: Pubkey,
+    pub applicant: Pubkey,
+    pub name: String,
+    pub deposit: u64,
+}
+
+#[event]
+pub struct TldApplicationApproved {
+    pub application: Pubkey,
+    pub tld: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct TldApplicationRejected {
+    pub application: Pubkey,
+    pub applicant: Pubkey,
+}
+
+#[event]
+pub struct TldApplicationWithdrawn {
+    pub application: Pubkey,
+    pub applicant: Pubkey,
+}
+
+#[event]
+pub struct TLDRevenueShareUpdated {
+    pub tld: Pubkey,
+    pub old_owner_share_bps: u16,
+    pub new_owner_share_bps: u16,
+}
+
+#[event]
+pub struct ReleaseAuctionUpdated {
+    pub tld: Pubkey,
+    pub start_price: u64,
+    pub period: i64,
+}
+
+#[event]
+pub struct DomainTransferred {
+    pub domain: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub sale_price: u64, // Zero for gifts
+}
+
+#[event]
+pub struct DomainReclaimed {
+    pub domain: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct DomainListed {
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct ListingUpdated {
+    pub domain: Pubkey,
+    pub old_price: u64,
+    pub new_price: u64,
+}
+
+#[event]
+pub struct ListingCancelled {
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct DomainSold {
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub royalty: u64,
+}
+
+#[event]
+pub struct OfferMade {
+    pub offer: Pubkey,
+    pub domain: Pubkey,
+    pub bidder: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub offer: Pubkey,
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub royalty: u64,
+}
+
+#[event]
+pub struct OfferCancelled {
+    pub offer: Pubkey,
+    pub domain: Pubkey,
+    pub bidder: Pubkey,
+}
+
+#[event]
+pub struct AuctionCreated {
+    pub auction: Pubkey,
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub reserve_price: u64,
+    pub min_increment: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub auction: Pubkey,
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub price: u64,
+    pub royalty: u64,
+}
+
+#[event]
+pub struct AuctionCancelled {
+    pub auction: Pubkey,
+    pub domain: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct TokenPriceUpdated {
+    pub tld: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,         // Zero when the token is no longer accepted
+    pub renewal_price: u64, // Zero when the token is no longer accepted
+}
+
+#[event]
+pub struct PricingScheduleUpdated {
+    pub tld: Pubkey,
+    pub tiers: Vec<PriceTier>,
+}
+
+#[event]
+pub struct PremiumNameUpdated {
+    pub tld: Pubkey,
+    pub name: String,
+    pub price: u64, // Zero when the premium price is removed
+}
+
+#[event]
+pub struct NameReserved {
+    pub tld: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct NameUnreserved {
+    pub tld: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct ReservedNameAssigned {
+    pub tld: Pubkey,
+    pub domain: Pubkey,
+    pub name: String,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct RecordSet {
+    pub domain: Pubkey,
+    pub record_type: RecordType,
+    pub data: Vec<u8>,
+}
+
+#[event]
+pub struct RecordDeleted {
+    pub domain: Pubkey,
+    pub record_type: RecordType,
+}
+
+#[event]
+pub struct PrimaryDomainSet {
+    pub owner: Pubkey,
+    pub domain: Pubkey,
+}
+
+#[event]
+pub struct PrimaryDomainCleared {
+    pub owner: Pubkey,
+    pub domain: Pubkey,
+}
+
+#[event]
+pub struct SubdomainConfigUpdated {
+    pub parent: Pubkey,
+    pub fee: u64,
+    pub is_open: bool,
+}
+
+#[event]
+pub struct SubdomainCreated {
+    pub parent: Pubkey,
+    pub subdomain: Pubkey,
+    pub owner: Pubkey,
+    pub is_revocable: bool,
+}
+
+#[event]
+pub struct SubdomainRemoved {
+    pub parent: Pubkey,
+    pub subdomain: Pubkey,
+    pub owner: Pubkey,
+}
+
 // Error codes
 #[error_code]
 pub enum ErrorCode {
@@ -531,4 +5443,96 @@ pub enum ErrorCode {
     NotDomainOwner,
     #[msg("Unauthorized")]
     Unauthorized,
+    #[msg("Record data is too long")]
+    RecordDataTooLong,
+    #[msg("Invalid record data for record type")]
+    InvalidRecordData,
+    #[msg("Subdomain issuance is not open")]
+    SubdomainsNotOpen,
+    #[msg("Subdomain is not revocable")]
+    SubdomainNotRevocable,
+    #[msg("Domain has expired")]
+    DomainExpired,
+    #[msg("Domain is in its grace period and can only be renewed")]
+    DomainInGracePeriod,
+    #[msg("Invalid grace period")]
+    InvalidGracePeriod,
+    #[msg("Domain has not expired past its grace period")]
+    DomainNotExpired,
+    #[msg("Number of years must be greater than zero")]
+    InvalidYears,
+    #[msg("Prepaid period exceeds the maximum allowed")]
+    PrepaidPeriodTooLong,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Domain name must use lowercase letters, digits and inner hyphens only")]
+    InvalidDomainName,
+    #[msg("TLD name must use lowercase letters, digits and inner hyphens only")]
+    InvalidTLDName,
+    #[msg("Too many price tiers")]
+    TooManyPriceTiers,
+    #[msg("Price tiers must have strictly increasing length bands")]
+    InvalidPriceTiers,
+    #[msg("Token is not accepted for this TLD")]
+    TokenNotAccepted,
+    #[msg("Token payment accounts are incomplete")]
+    IncompleteTokenPayment,
+    #[msg("Token payment account does not match")]
+    InvalidPaymentAccount,
+    #[msg("Price feed account does not match the service price feed")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is unavailable or not positive")]
+    InvalidOraclePrice,
+    #[msg("Oracle price is too old")]
+    StaleOraclePrice,
+    #[msg("Oracle price confidence interval is too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Invalid price feed configuration")]
+    InvalidOracleConfig,
+    #[msg("Listing no longer holds the domain")]
+    ListingInactive,
+    #[msg("Price exceeds the buyer's maximum")]
+    PriceAboveMaximum,
+    #[msg("Offer expiry must be in the future")]
+    InvalidOfferExpiry,
+    #[msg("Offer has expired")]
+    OfferExpired,
+    #[msg("Offer has not expired")]
+    OfferNotExpired,
+    #[msg("Invalid auction end time")]
+    InvalidAuctionEndTime,
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended")]
+    AuctionNotEnded,
+    #[msg("Auction no longer holds the domain")]
+    AuctionInactive,
+    #[msg("Bid is below the minimum")]
+    BidTooLow,
+    #[msg("Account does not match the auction's high bidder")]
+    InvalidBidder,
+    #[msg("Auction already has bids")]
+    AuctionHasBids,
+    #[msg("Invalid release auction period")]
+    InvalidReleasePeriod,
+    #[msg("Revenue share cannot exceed 10000 basis points")]
+    InvalidRevenueShare,
+    #[msg("TLD already exists")]
+    TLDAlreadyExists,
+    #[msg("TLD is already active")]
+    TLDAlreadyActive,
+    #[msg("Name is reserved")]
+    NameIsReserved,
+    #[msg("No authority change is pending")]
+    NoPendingAuthority,
+    #[msg("No treasury change is pending")]
+    NoPendingTreasury,
+    #[msg("This operation is currently paused")]
+    ServicePaused,
+    #[msg("Unknown pause flags")]
+    InvalidPauseFlags,
+    #[msg("Account is already in the current layout")]
+    AccountAlreadyMigrated,
+    #[msg("Domain NFT mint can still be minted or frozen")]
+    DomainMintNotLocked,
 }
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+pub const MAX_DOMAIN_NAME_LEN: usize = 63;
+pub const MAX_TLD_NAME_LEN: usize = 10;
+
+// Punycode parameters (RFC 3492)
+const BASE: u32 = 36;
+const TMIN: u32 = 1;
+const TMAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// Lowercases ASCII letters so `Foo` and `foo` derive the same PDA.
+/// Anything outside the LDH charset is left as-is for validation to reject.
+pub fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase()
+}
+
+/// Validates a normalized domain or subdomain label. With `allow_idna`,
+/// `xn--` labels are accepted if they hold valid punycode.
+pub fn validate_domain_name(name: &str, allow_idna: bool) -> Result<()> {
+    require!(!name.is_empty(), ErrorCode::DomainNameTooShort);
+    require!(name.len() <= MAX_DOMAIN_NAME_LEN, ErrorCode::DomainNameTooLong);
+    require!(is_valid_label(name, allow_idna), ErrorCode::InvalidDomainName);
+    Ok(())
+}
+
+/// Validates a normalized TLD name. TLDs are plain LDH labels without IDNA.
+pub fn validate_tld_name(name: &str) -> Result<()> {
+    require!(!name.is_empty(), ErrorCode::InvalidTLDName);
+    require!(name.len() <= MAX_TLD_NAME_LEN, ErrorCode::TLDNameTooLong);
+    require!(is_valid_label(name, false), ErrorCode::InvalidTLDName);
+    Ok(())
+}
+
+fn is_valid_label(label: &str, allow_idna: bool) -> bool {
+    let is_ldh = label
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+    if !is_ldh || label.starts_with('-') || label.ends_with('-') {
+        return false;
+    }
+
+    // Hyphens in the third and fourth positions are reserved for encodings like punycode
+    if label.len() >= 4 && &label[2..4] == "--" {
+        return allow_idna
+            && label.starts_with("xn--")
+            && matches!(
+                decode_punycode(&label[4..]),
+                Some(decoded) if decoded.iter().any(|c| !c.is_ascii())
+            );
+    }
+
+    true
+}
+
+fn adapt(mut delta: u32, num_points: u32, first_time: bool) -> u32 {
+    delta /= if first_time { DAMP } else { 2 };
+    delta += delta / num_points;
+
+    let mut k = 0;
+    while delta > ((BASE - TMIN) * TMAX) / 2 {
+        delta /= BASE - TMIN;
+        k += BASE;
+    }
+
+    k + ((BASE - TMIN + 1) * delta) / (delta + SKEW)
+}
+
+/// Decodes an RFC 3492 punycode string (without the `xn--` prefix),
+/// returning `None` if it is malformed.
+fn decode_punycode(input: &str) -> Option<Vec<char>> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(position) => (&input[..position], &input[position + 1..]),
+        None => ("", input),
+    };
+
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = extended.bytes().peekable();
+
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = BASE;
+
+        loop {
+            let digit = match digits.next()? {
+                b @ b'a'..=b'z' => (b - b'a') as u32,
+                b @ b'0'..=b'9' => (b - b'0') as u32 + 26,
+                _ => return None,
+            };
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+
+            let t = if k <= bias {
+                TMIN
+            } else if k >= bias + TMAX {
+                TMAX
+            } else {
+                k - bias
+            };
+            if digit < t {
+                break;
+            }
+
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+
+        let num_points = output.len() as u32 + 1;
+        bias = adapt(i - old_i, num_points, old_i == 0);
+        n = n.checked_add(i / num_points)?;
+        i %= num_points;
+
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::name_hash;
+
+    fn assert_rejected(result: Result<()>, expected: ErrorCode) {
+        assert_eq!(result.unwrap_err(), error!(expected));
+    }
+
+    #[test]
+    fn accepts_ldh_labels() {
+        for label in ["a", "alice", "a1", "0", "my-name", "a--b", "123-456"] {
+            assert!(validate_domain_name(label, false).is_ok(), "{label}");
+        }
+    }
+
+    #[test]
+    fn accepts_valid_punycode_only_with_idna() {
+        for label in ["xn--mnchen-3ya", "xn--bcher-kva", "xn--wgv71a", "xn--ida", "xn--fa-hia"] {
+            assert!(validate_domain_name(label, true).is_ok(), "{label}");
+            assert_rejected(validate_domain_name(label, false), ErrorCode::InvalidDomainName);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_punycode() {
+        for label in [
+            "xn--",             // Nothing to decode
+            "xn--abc-",         // Decodes to plain ASCII
+            "xn--99999999",     // Truncated variable-length integer
+            "xn--ab-c-9",       // Truncated after the basic code points
+            "xn--zzzzzzzzzzzz", // Code point overflow
+        ] {
+            assert_rejected(validate_domain_name(label, true), ErrorCode::InvalidDomainName);
+        }
+    }
+
+    #[test]
+    fn rejects_leading_and_trailing_hyphens() {
+        for label in ["-alice", "alice-", "-", "-xn--ida"] {
+            assert_rejected(validate_domain_name(label, true), ErrorCode::InvalidDomainName);
+        }
+    }
+
+    #[test]
+    fn rejects_hyphens_in_third_and_fourth_positions() {
+        for label in ["ab--c", "ab--", "zz--mnchen-3ya"] {
+            assert_rejected(validate_domain_name(label, true), ErrorCode::InvalidDomainName);
+        }
+    }
+
+    #[test]
+    fn rejects_characters_outside_ldh() {
+        for label in ["Alice", "al ice", "al_ice", "al.ice", "ålice"] {
+            assert_rejected(validate_domain_name(label, true), ErrorCode::InvalidDomainName);
+        }
+    }
+
+    #[test]
+    fn enforces_domain_length_limits() {
+        assert_rejected(validate_domain_name("", false), ErrorCode::DomainNameTooShort);
+        assert!(validate_domain_name(&"a".repeat(MAX_DOMAIN_NAME_LEN), false).is_ok());
+        assert_rejected(
+            validate_domain_name(&"a".repeat(MAX_DOMAIN_NAME_LEN + 1), false),
+            ErrorCode::DomainNameTooLong,
+        );
+    }
+
+    #[test]
+    fn validates_tld_names() {
+        assert!(validate_tld_name("pulse").is_ok());
+        assert!(validate_tld_name(&"a".repeat(MAX_TLD_NAME_LEN)).is_ok());
+        assert_rejected(validate_tld_name(""), ErrorCode::InvalidTLDName);
+        assert_rejected(
+            validate_tld_name(&"a".repeat(MAX_TLD_NAME_LEN + 1)),
+            ErrorCode::TLDNameTooLong,
+        );
+        assert_rejected(validate_tld_name("-pulse"), ErrorCode::InvalidTLDName);
+        assert_rejected(validate_tld_name("xn--ida"), ErrorCode::InvalidTLDName);
+    }
+
+    #[test]
+    fn normalization_makes_names_case_insensitive() {
+        assert_eq!(normalize("Alice"), "alice");
+        assert_eq!(normalize("XN--MNCHEN-3YA"), "xn--mnchen-3ya");
+        assert_eq!(name_hash("ALICE"), name_hash("alice"));
+        assert_eq!(name_hash("Alice"), name_hash(&normalize("Alice")));
+        assert_ne!(name_hash("alice"), name_hash("alicf"));
+        assert!(validate_domain_name(&normalize("Alice"), false).is_ok());
+    }
+}
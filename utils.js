@@ -136,6 +136,22 @@ async function getDomainPDA(domainName, tldName, programId) {
     return pda;
 }
 
+/**
+ * Get the associated token account of a wallet for a mint
+ */
+async function getAssociatedTokenAddress(mint, owner) {
+    const { PublicKey } = window.solanaWeb3;
+    const [address] = await PublicKey.findProgramAddress(
+        [
+            owner.toBuffer(),
+            new PublicKey(TOKEN_PROGRAM_ID).toBuffer(),
+            mint.toBuffer()
+        ],
+        new PublicKey(ASSOCIATED_TOKEN_PROGRAM_ID)
+    );
+    return address;
+}
+
 /**
  * Show loading indicator
  */
@@ -222,6 +238,7 @@ if (typeof module !== 'undefined' && module.exports) {
         getServicePDA,
         getTLDPDA,
         getDomainPDA,
+        getAssociatedTokenAddress,
         showLoading,
         hideLoading,
         showNotification,